@@ -5,7 +5,7 @@ use std::time::Duration;
 
 use criterion::{criterion_group, criterion_main, Criterion, SamplingMode};
 use phf::phf_map;
-use trie_match::trie_match;
+use trie_match::{trie_match, BuildOptions, Sparse};
 
 fn load_input(path: impl AsRef<Path>) -> Vec<String> {
     let mut result = vec![];
@@ -715,6 +715,141 @@ fn criterion_html_elements(c: &mut Criterion) {
         });
     });
 
+    group.bench_function("trie_match_length_rand", |b| {
+        b.iter(|| {
+            let mut x = 0;
+            for s in &html_elements {
+                trie_match!(
+                    #[strategy(length)]
+                    match s.as_str() {
+                        "bdo" | "rb" | "th" | "ul" | "pre" | "mark" | "em" | "search" | "head"
+                        | "li" | "del" | "details" | "p" | "bdi" | "time" | "area" | "br"
+                        | "var" | "aside" | "main" | "tfoot" | "hr" | "label" | "rp"
+                        | "menuitem" => {
+                            x += 3141;
+                        }
+                        "portal" | "wbr" | "cite" | "ins" | "footer" | "table" | "address"
+                        | "div" | "optgroup" | "dd" | "samp" | "map" | "xmp" | "embed"
+                        | "strong" | "dialog" | "colgroup" | "input" | "figure" | "body"
+                        | "strike" | "audio" | "marquee" | "noscript" | "form" => {
+                            x += 5926;
+                        }
+                        "nobr" | "font" | "textarea" | "tbody" | "picture" | "legend" | "img"
+                        | "progress" | "meter" | "script" | "dt" | "summary" | "ol" | "acronym"
+                        | "header" | "title" | "span" | "abbr" | "hgroup" | "meta"
+                        | "plaintext" | "base" | "sub" | "select" | "s" => {
+                            x += 5358;
+                        }
+                        "output" | "datalist" | "article" | "param" | "blockquote" | "i" | "tr"
+                        | "html" | "section" | "link" | "small" | "canvas" | "option" | "dir"
+                        | "col" | "noembed" | "rtc" | "big" | "figcaption" | "kbd" | "b" | "u"
+                        | "a" | "td" | "center" => {
+                            x += 9793;
+                        }
+                        "menu" | "template" | "data" | "image" | "fieldset" | "slot" | "q"
+                        | "thead" | "nav" | "style" | "button" | "video" | "dl" | "caption"
+                        | "ruby" | "tt" | "dfn" | "code" | "source" | "h1" | "iframe" | "sup"
+                        | "noframes" | "frameset" | "track" | "frame" | "rt" | "object" => {
+                            x += 2384;
+                        }
+                        _ => {}
+                    }
+                )
+            }
+            x
+        });
+    });
+
+    group.bench_function("trie_match_decision_tree_rand", |b| {
+        b.iter(|| {
+            let mut x = 0;
+            for s in &html_elements {
+                trie_match!(
+                    #[strategy(decision_tree)]
+                    match s.as_str() {
+                        "bdo" | "rb" | "th" | "ul" | "pre" | "mark" | "em" | "search" | "head"
+                        | "li" | "del" | "details" | "p" | "bdi" | "time" | "area" | "br"
+                        | "var" | "aside" | "main" | "tfoot" | "hr" | "label" | "rp"
+                        | "menuitem" => {
+                            x += 3141;
+                        }
+                        "portal" | "wbr" | "cite" | "ins" | "footer" | "table" | "address"
+                        | "div" | "optgroup" | "dd" | "samp" | "map" | "xmp" | "embed"
+                        | "strong" | "dialog" | "colgroup" | "input" | "figure" | "body"
+                        | "strike" | "audio" | "marquee" | "noscript" | "form" => {
+                            x += 5926;
+                        }
+                        "nobr" | "font" | "textarea" | "tbody" | "picture" | "legend" | "img"
+                        | "progress" | "meter" | "script" | "dt" | "summary" | "ol" | "acronym"
+                        | "header" | "title" | "span" | "abbr" | "hgroup" | "meta"
+                        | "plaintext" | "base" | "sub" | "select" | "s" => {
+                            x += 5358;
+                        }
+                        "output" | "datalist" | "article" | "param" | "blockquote" | "i" | "tr"
+                        | "html" | "section" | "link" | "small" | "canvas" | "option" | "dir"
+                        | "col" | "noembed" | "rtc" | "big" | "figcaption" | "kbd" | "b" | "u"
+                        | "a" | "td" | "center" => {
+                            x += 9793;
+                        }
+                        "menu" | "template" | "data" | "image" | "fieldset" | "slot" | "q"
+                        | "thead" | "nav" | "style" | "button" | "video" | "dl" | "caption"
+                        | "ruby" | "tt" | "dfn" | "code" | "source" | "h1" | "iframe" | "sup"
+                        | "noframes" | "frameset" | "track" | "frame" | "rt" | "object" => {
+                            x += 2384;
+                        }
+                        _ => {}
+                    }
+                )
+            }
+            x
+        });
+    });
+
+    group.bench_function("trie_match_perfect_hash_rand", |b| {
+        b.iter(|| {
+            let mut x = 0;
+            for s in &html_elements {
+                trie_match!(
+                    #[strategy(perfect_hash)]
+                    match s.as_str() {
+                        "bdo" | "rb" | "th" | "ul" | "pre" | "mark" | "em" | "search" | "head"
+                        | "li" | "del" | "details" | "p" | "bdi" | "time" | "area" | "br"
+                        | "var" | "aside" | "main" | "tfoot" | "hr" | "label" | "rp"
+                        | "menuitem" => {
+                            x += 3141;
+                        }
+                        "portal" | "wbr" | "cite" | "ins" | "footer" | "table" | "address"
+                        | "div" | "optgroup" | "dd" | "samp" | "map" | "xmp" | "embed"
+                        | "strong" | "dialog" | "colgroup" | "input" | "figure" | "body"
+                        | "strike" | "audio" | "marquee" | "noscript" | "form" => {
+                            x += 5926;
+                        }
+                        "nobr" | "font" | "textarea" | "tbody" | "picture" | "legend" | "img"
+                        | "progress" | "meter" | "script" | "dt" | "summary" | "ol" | "acronym"
+                        | "header" | "title" | "span" | "abbr" | "hgroup" | "meta"
+                        | "plaintext" | "base" | "sub" | "select" | "s" => {
+                            x += 5358;
+                        }
+                        "output" | "datalist" | "article" | "param" | "blockquote" | "i" | "tr"
+                        | "html" | "section" | "link" | "small" | "canvas" | "option" | "dir"
+                        | "col" | "noembed" | "rtc" | "big" | "figcaption" | "kbd" | "b" | "u"
+                        | "a" | "td" | "center" => {
+                            x += 9793;
+                        }
+                        "menu" | "template" | "data" | "image" | "fieldset" | "slot" | "q"
+                        | "thead" | "nav" | "style" | "button" | "video" | "dl" | "caption"
+                        | "ruby" | "tt" | "dfn" | "code" | "source" | "h1" | "iframe" | "sup"
+                        | "noframes" | "frameset" | "track" | "frame" | "rt" | "object" => {
+                            x += 2384;
+                        }
+                        _ => {}
+                    }
+                )
+            }
+            x
+        });
+    });
+
     group.bench_function("trie_match_1", |b| {
         b.iter(|| {
             let mut x = 0;
@@ -798,6 +933,54 @@ fn criterion_html_elements(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, criterion_word100, criterion_html_elements);
+fn criterion_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build_100k");
+    group.sample_size(10);
+    group.warm_up_time(Duration::from_millis(500));
+    group.measurement_time(Duration::from_secs(10));
+    group.sampling_mode(SamplingMode::Flat);
+
+    // 100,000 random keywords of 4 to 19 lowercase letters generated by an LCG. Most states
+    // are leaves or have a single child, so base values are sparse among the positions.
+    let mut trie = Sparse::new();
+    let mut x = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        x = x
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        x >> 33
+    };
+    for value in 0..100_000 {
+        let len = 4 + next() % 16;
+        let keyword: String = (0..len)
+            .map(|_| char::from(b'a' + u8::try_from(next() % 26).unwrap()))
+            .collect();
+        trie.add(keyword, value);
+    }
+
+    group.bench_function("double_array", |b| {
+        b.iter(|| trie.build_double_array_trie(u32::MAX));
+    });
+    group.bench_function("tail_compressed", |b| {
+        b.iter(|| trie.build_tail_compressed(u32::MAX));
+    });
+    group.bench_function("path_compressed", |b| {
+        b.iter(|| {
+            let options = BuildOptions {
+                tail_compression: true,
+                path_compression: true,
+                ..BuildOptions::default()
+            };
+            trie.build_with_options(u32::MAX, options)
+        });
+    });
+}
+
+criterion_group!(
+    benches,
+    criterion_word100,
+    criterion_html_elements,
+    criterion_build
+);
 
 criterion_main!(benches);
@@ -1,5 +1,3 @@
-#![cfg_attr(feature = "cfg_attribute", feature(proc_macro_expand))]
-
 //! # `trie_match! {}`
 //!
 //! This macro speeds up Rust's `match` expression for comparing strings by using a compact
@@ -68,387 +66,260 @@ assert_eq!(result, 4);
 //! * The wildcard is evaluated last. (The normal `match` expression does not
 //!   match patterns after the wildcard.)
 //! * Guards are unavailable.
+//!
+//! ## Binding the matched literal
+//!
+//! A binding such as `pat @ ("abd" | "bcde")` borrows the query, so it cannot outlive the query.
+//! The `#[literal(...)]` attribute on an arm binds the matched pattern literal instead. It is bound
+//! as `&'static str` if all patterns of the arm are string literals, and as `&'static [u8]`
+//! otherwise.
+//!
+//! ```
+//! use trie_match::trie_match;
+//!
+//! let x = String::from("bcde");
+//!
+//! let result: &'static str = trie_match! {
+//!     match x.as_str() {
+//!         #[literal(pat)]
+//!         "abd" | "bcde" => pat,
+//!         _ => "",
+//!     }
+//! };
+//! drop(x);
+//!
+//! assert_eq!(result, "bcde");
+//! ```
+//!
+//! ## Suggesting similar patterns
+//!
+//! The `#[suggest(...)]` attribute on the wildcard arm binds an iterator over patterns within the
+//! given edit distance from the query, which is useful for "did you mean" messages. The iterator
+//! yields tuples of a pattern and its distance in lexicographic order. Patterns are bound as
+//! `&'static str` if all patterns are string literals, and as `&'static [u8]` otherwise. If
+//! `distance` is omitted, 2 is used. The distance is counted in bytes.
+//!
+//! ```
+//! use trie_match::trie_match;
+//!
+//! let x = "--verbos";
+//!
+//! let result = trie_match! {
+//!     match x {
+//!         "--verbose" => None,
+//!         "--quiet" => None,
+//!         #[suggest(candidates, distance = 2)]
+//!         _ => candidates.min_by_key(|&(_, d)| d).map(|(pat, _)| pat),
+//!     }
+//! };
+//!
+//! assert_eq!(result, Some("--verbose"));
+//! ```
+//!
+//! ## Const-compatible mode
+//!
+//! The generated code is not available in const contexts by default. The `#[const_compatible]`
+//! attribute on the match expression generates code that only uses const operations, so that the
+//! macro can be used in `const fn`. The query must be `&str` if all patterns are string literals,
+//! and `&[u8]` otherwise. The `#[suggest(...)]` attribute is unavailable in this mode.
+//!
+//! ```
+//! use trie_match::trie_match;
+//!
+//! const fn keyword_id(x: &str) -> u32 {
+//!     trie_match! {
+//!         #[const_compatible]
+//!         match x {
+//!             "fn" => 1,
+//!             "if" | "impl" => 2,
+//!             _ => 0,
+//!         }
+//!     }
+//! }
+//!
+//! const IMPL: u32 = keyword_id("impl");
+//!
+//! assert_eq!(IMPL, 2);
+//! ```
+//!
+//! ## Alphabet remapping
+//!
+//! The double-array uses bytes as offsets from base values, so patterns with non-ASCII characters
+//! spread states over a wide range. The `#[alphabet_remapping]` attribute on the match expression
+//! assigns dense codes to the bytes that appear in the patterns, and the generated code looks up
+//! the code of each byte in a 256-entry table. This shrinks the tables if the patterns contain a
+//! wide range of bytes.
+//!
+//! ```
+//! use trie_match::trie_match;
+//!
+//! let x = "\u{65e5}\u{672c}\u{8a9e}";
+//!
+//! let result = trie_match! {
+//!     #[alphabet_remapping]
+//!     match x {
+//!         "\u{65e5}\u{672c}" | "\u{65e5}\u{672c}\u{8a9e}" => 0,
+//!         "\u{4e2d}\u{56fd}" => 1,
+//!         _ => 2,
+//!     }
+//! };
+//!
+//! assert_eq!(result, 0);
+//! ```
+//!
+//! ## Minimization
+//!
+//! Patterns such as inflected word forms share many suffixes, but a trie stores each of them
+//! separately. The `#[minimize]` attribute on the match expression merges equivalent subtrees,
+//! i.e., subtrees with the same suffixes leading to the same arms, so that they are stored only
+//! once. Arms with the `literal` attribute are not merged because their patterns must be
+//! distinguished.
+//!
+//! ```
+//! use trie_match::trie_match;
+//!
+//! let x = "talks";
+//!
+//! let result = trie_match! {
+//!     #[minimize]
+//!     match x {
+//!         "walk" | "talk" => 0,
+//!         "walks" | "talks" => 1,
+//!         "walked" | "talked" => 2,
+//!         _ => 3,
+//!     }
+//! };
+//!
+//! assert_eq!(result, 1);
+//! ```
+//!
+//! ## Strategies
+//!
+//! The macro expands the match expression with one of the following strategies.
+//!
+//! * `double_array`: traverses the double-array byte by byte. Long unbranched paths shared by
+//!   several patterns are compared 4 or 8 bytes at a time with constant words. The table is
+//!   padded, or ends with a sentinel slot, so that transitions read it without bounds checks.
+//! * `length`: dispatches on the length of the query, and then compares the query only with the
+//!   patterns of the same length. This is faster if most patterns have distinct lengths.
+//! * `decision_tree`: expands into nested `match`es on bytes of the query without tables. Each
+//!   node tests the byte position that splits the remaining patterns best. This is suited to
+//!   small pattern sets.
+//! * `match`: expands into a normal `match` expression on the bytes of the query.
+//! * `perfect_hash`: hashes the query with a perfect hash function of the patterns found at compile
+//!   time, and then compares the query only with the pattern of the same hash. This is suited to
+//!   large pattern sets sharing few prefixes. The expansion fails if no perfect hash function is
+//!   found.
+//!
+//! By default, the strategy is selected by a cost model at compile time. It estimates the cost of
+//! a query from the number and lengths of the patterns, the branching factor of the trie, and the
+//! sizes of the tables and the code. The `#[strategy(...)]` attribute on the match expression
+//! overrides the choice. The `alphabet_remapping` and `minimize` attributes only affect the
+//! `double_array` strategy, so they also select it. If the cost model selects `perfect_hash` but no
+//! perfect hash function is found, `double_array` is used instead. The strategy selected for a
+//! function defined by [`trie_match_fn!`] is available as `STRATEGY` in the module of the same
+//! name, together with `NUM_SLOTS`, `NUM_STATES`, and `FILL_RATIO` of the double-array, which help
+//! track the size of the tables. The `#[report_strategy]` attribute on the match expression
+//! reports the selected strategy as a warning at compile time, together with the numbers of
+//! states and slots of the double-array if it is used. Since procedural macros cannot emit
+//! warnings on stable Rust, the warning is the deprecation note of a generated function, and
+//! `#[allow(deprecated)]` silences it.
+//!
+//! ```
+//! use trie_match::trie_match;
+//!
+//! let x = "span";
+//!
+//! let result = trie_match! {
+//!     #[strategy(length)]
+//!     match x {
+//!         "a" | "abbr" => 0,
+//!         "div" | "span" => 1,
+//!         _ => 2,
+//!     }
+//! };
+//!
+//! assert_eq!(result, 1);
+//! ```
+//!
+//! ## Reusable matcher functions
+//!
+//! Each `trie_match!` has its own tables, so the same match expression written in several places
+//! is duplicated in the binary. [`trie_match_fn!`] defines a named function whose body is the
+//! match expression, and a module of the same name containing the patterns as `PATTERNS`.
+//!
+//! ```
+//! use trie_match::trie_match_fn;
+//!
+//! trie_match_fn! {
+//!     #[inline(never)]
+//!     pub fn classify(x: &str) -> u8 {
+//!         match x {
+//!             "a" => 0,
+//!             "abc" | "bc" => 1,
+//!             _ => 2,
+//!         }
+//!     }
+//! }
+//!
+//! assert_eq!(classify("bc"), 1);
+//! assert_eq!(classify::PATTERNS, ["a", "abc", "bc"]);
+//! ```
+//!
+//! Modules cannot be defined in impl blocks, so [`trie_match_fn!`] is not available for methods.
+//! Define the function outside the impl block, and call it from the method instead.
+//!
+//! ```
+//! use trie_match::trie_match_fn;
+//!
+//! trie_match_fn! {
+//!     fn keyword_id(x: &str) -> Option<u8> {
+//!         match x {
+//!             "fn" => Some(0),
+//!             "if" => Some(1),
+//!             _ => None,
+//!         }
+//!     }
+//! }
+//!
+//! struct Lexer;
+//!
+//! impl Lexer {
+//!     fn keyword(&self, x: &str) -> Option<u8> {
+//!         keyword_id(x)
+//!     }
+//! }
+//!
+//! assert_eq!(Lexer.keyword("if"), Some(1));
+//! assert_eq!(keyword_id::PATTERNS, ["fn", "if"]);
+//! ```
+#![cfg_attr(
+    feature = "alloc",
+    doc = r#"
+## Building double-arrays at run time
 
-mod trie;
-
-extern crate proc_macro;
-
-use std::collections::HashMap;
-
-use proc_macro2::{Span, TokenStream};
-use quote::{format_ident, quote};
-use syn::{
-    parse_macro_input, spanned::Spanned, Arm, Error, Expr, ExprLit, ExprMatch, Lit, Pat, PatIdent,
-    PatOr, PatReference, PatSlice, PatWild,
-};
-
-#[cfg(feature = "cfg_attribute")]
-use proc_macro2::Ident;
-#[cfg(feature = "cfg_attribute")]
-use syn::{Attribute, Meta};
-
-use crate::trie::Sparse;
-
-static ERROR_UNEXPECTED_PATTERN: &str =
-    "`trie_match` only supports string literals, byte string literals, and u8 slices as patterns";
-static ERROR_ATTRIBUTE_NOT_SUPPORTED: &str = "attribute not supported here";
-static ERROR_GUARD_NOT_SUPPORTED: &str = "match guard not supported";
-static ERROR_UNREACHABLE_PATTERN: &str = "unreachable pattern";
-static ERROR_PATTERN_NOT_COVERED: &str = "non-exhaustive patterns: `_` not covered";
-static ERROR_EXPECTED_U8_LITERAL: &str = "expected `u8` integer literal";
-static ERROR_VARIABLE_NOT_MATCH: &str = "variable is not bound in all patterns";
-
-#[cfg(not(feature = "cfg_attribute"))]
-static ERROR_ATTRIBUTE_NOT_SUPPORTED_CFG: &str =
-    "attribute not supported here\nnote: consider enabling the `cfg_attribute` feature: \
-    https://docs.rs/trie-match/latest/trie_match/#cfg-attribute";
-
-#[cfg(feature = "cfg_attribute")]
-static ERROR_NOT_CFG_ATTRIBUTE: &str = "only supports the cfg attribute";
-
-/// Converts a literal pattern into a byte sequence.
-fn convert_literal_pattern(pat: &ExprLit) -> Result<Option<Vec<u8>>, Error> {
-    let ExprLit { attrs, lit } = pat;
-    if let Some(attr) = attrs.first() {
-        return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
-    }
-    match lit {
-        Lit::Str(s) => Ok(Some(s.value().into())),
-        Lit::ByteStr(s) => Ok(Some(s.value())),
-        _ => Err(Error::new(lit.span(), ERROR_UNEXPECTED_PATTERN)),
-    }
-}
-
-/// Converts a slice pattern into a byte sequence.
-fn convert_slice_pattern(pat: &PatSlice) -> Result<Option<Vec<u8>>, Error> {
-    let PatSlice { attrs, elems, .. } = pat;
-    if let Some(attr) = attrs.first() {
-        return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
-    }
-    let mut result = vec![];
-    for elem in elems {
-        match elem {
-            Pat::Lit(ExprLit { attrs, lit }) => {
-                if let Some(attr) = attrs.first() {
-                    return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
-                }
-                match lit {
-                    Lit::Int(i) => {
-                        let int_type = i.suffix();
-                        if int_type != "u8" && !int_type.is_empty() {
-                            return Err(Error::new(i.span(), ERROR_EXPECTED_U8_LITERAL));
-                        }
-                        result.push(i.base10_parse::<u8>()?);
-                    }
-                    Lit::Byte(b) => {
-                        result.push(b.value());
-                    }
-                    _ => {
-                        return Err(Error::new(elem.span(), ERROR_EXPECTED_U8_LITERAL));
-                    }
-                }
-            }
-            _ => {
-                return Err(Error::new(elem.span(), ERROR_EXPECTED_U8_LITERAL));
-            }
-        }
-    }
-    Ok(Some(result))
-}
-
-/// Checks a wildcard pattern and returns `None`.
-///
-/// The reason the type is `Result<Option<Vec<u8>>, Error>` instead of `Result<(), Error>` is for
-/// consistency with other functions.
-fn convert_wildcard_pattern(pat: &PatWild) -> Result<Option<Vec<u8>>, Error> {
-    let PatWild { attrs, .. } = pat;
-    if let Some(attr) = attrs.first() {
-        return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
-    }
-    Ok(None)
-}
-
-/// Converts a reference pattern (e.g. `&[0, 1, ...]`) into a byte sequence.
-fn convert_reference_pattern(pat: &PatReference) -> Result<Option<Vec<u8>>, Error> {
-    let PatReference { attrs, pat, .. } = pat;
-    if let Some(attr) = attrs.first() {
-        return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
-    }
-    match &**pat {
-        Pat::Lit(pat) => convert_literal_pattern(pat),
-        Pat::Slice(pat) => convert_slice_pattern(pat),
-        Pat::Reference(pat) => convert_reference_pattern(pat),
-        _ => Err(Error::new(pat.span(), ERROR_UNEXPECTED_PATTERN)),
-    }
-}
-
-struct PatternBytes {
-    /// Bound variable identifier.
-    ident: Option<PatIdent>,
-
-    /// Byte sequence of this pattern. `None` is for a wildcard.
-    bytes: Option<Vec<u8>>,
-}
-
-impl PatternBytes {
-    const fn new(ident: Option<PatIdent>, bytes: Option<Vec<u8>>) -> Self {
-        Self { ident, bytes }
-    }
-}
-
-/// Retrieves pattern strings from the given token.
-///
-/// None indicates a wild card pattern (`_`).
-fn retrieve_match_patterns(
-    pat: &Pat,
-    ident: Option<PatIdent>,
-    pat_bytes_set: &mut Vec<PatternBytes>,
-    pat_set: &mut Vec<Pat>,
-) -> Result<(), Error> {
-    match pat {
-        Pat::Lit(lit) => {
-            pat_set.push(pat.clone());
-            pat_bytes_set.push(PatternBytes::new(ident, convert_literal_pattern(lit)?));
-        }
-        Pat::Slice(slice) => {
-            pat_set.push(pat.clone());
-            pat_bytes_set.push(PatternBytes::new(ident, convert_slice_pattern(slice)?));
-        }
-        Pat::Wild(pat) => {
-            pat_bytes_set.push(PatternBytes::new(ident, convert_wildcard_pattern(pat)?));
-        }
-        Pat::Reference(reference) => {
-            pat_set.push(pat.clone());
-            pat_bytes_set.push(PatternBytes::new(
-                ident,
-                convert_reference_pattern(reference)?,
-            ));
-        }
-        Pat::Ident(pat) => {
-            if let Some(attr) = pat.attrs.first() {
-                return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
-            }
-            let mut pat = pat.clone();
-            if let Some((_, subpat)) = pat.subpat.take() {
-                retrieve_match_patterns(&subpat, Some(pat), pat_bytes_set, pat_set)?;
-            } else {
-                pat_bytes_set.push(PatternBytes::new(Some(pat), None));
-            }
-        }
-        Pat::Paren(pat) => {
-            retrieve_match_patterns(&pat.pat, ident, pat_bytes_set, pat_set)?;
-        }
-        Pat::Or(PatOr {
-            attrs,
-            leading_vert: None,
-            cases,
-        }) => {
-            if let Some(attr) = attrs.first() {
-                return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
-            }
-            for pat in cases {
-                retrieve_match_patterns(pat, ident.clone(), pat_bytes_set, pat_set)?;
-            }
-        }
-        _ => {
-            return Err(Error::new(pat.span(), ERROR_UNEXPECTED_PATTERN));
-        }
-    }
-    Ok(())
-}
-
-#[cfg(feature = "cfg_attribute")]
-fn evaluate_cfg_attribute(attrs: &[Attribute]) -> Result<bool, Error> {
-    for attr in attrs {
-        let ident = attr.path().get_ident().map(Ident::to_string);
-        if ident.as_deref() == Some("cfg") {
-            if let Meta::List(list) = &attr.meta {
-                let tokens = &list.tokens;
-                let cfg_macro: proc_macro::TokenStream = quote! { cfg!(#tokens) }.into();
-                let expr = cfg_macro
-                    .expand_expr()
-                    .map_err(|e| Error::new(tokens.span(), e.to_string()))?;
-                if expr.to_string() == "false" {
-                    return Ok(false);
-                }
-                continue;
-            }
-        }
-        return Err(Error::new(attr.span(), ERROR_NOT_CFG_ATTRIBUTE));
-    }
-    Ok(true)
-}
-
-struct MatchInfo {
-    bodies: Vec<Expr>,
-    pattern_map: HashMap<Vec<u8>, usize>,
-    wildcard_idx: usize,
-    bound_vals: Vec<Option<PatIdent>>,
-    pat_set: Vec<Pat>,
-}
-
-fn parse_match_arms(arms: Vec<Arm>) -> Result<MatchInfo, Error> {
-    let mut pattern_map = HashMap::new();
-    let mut wildcard_idx = None;
-    let mut bound_vals = vec![];
-    let mut bodies = vec![];
-    let mut pat_set = vec![];
-    let mut i = 0;
-    #[allow(clippy::explicit_counter_loop)]
-    for Arm {
-        attrs,
-        pat,
-        guard,
-        body,
-        ..
-    } in arms
-    {
-        #[cfg(feature = "cfg_attribute")]
-        if !evaluate_cfg_attribute(&attrs)? {
-            continue;
-        }
-        #[cfg(not(feature = "cfg_attribute"))]
-        if let Some(attr) = attrs.first() {
-            return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED_CFG));
-        }
+The double-array builder used by the macros is available as [`Sparse`] when the `alloc` feature
+is enabled, which is the default. It produces the same compact representation as the generated
+code, so it can be used at run time or in `build.rs`. The generated code requires neither
+`alloc` nor `std`.
 
-        if let Some((if_token, _)) = guard {
-            return Err(Error::new(if_token.span(), ERROR_GUARD_NOT_SUPPORTED));
-        }
-        let mut pat_bytes_set = vec![];
-        retrieve_match_patterns(&pat, None, &mut pat_bytes_set, &mut pat_set)?;
-        let bound_val = pat_bytes_set[0].ident.clone();
-        for PatternBytes { ident, bytes } in pat_bytes_set {
-            if ident != bound_val {
-                return Err(Error::new(
-                    ident.or(bound_val).unwrap().span(),
-                    ERROR_VARIABLE_NOT_MATCH,
-                ));
-            }
-            if let Some(bytes) = bytes {
-                if pattern_map.contains_key(&bytes) {
-                    return Err(Error::new(pat.span(), ERROR_UNREACHABLE_PATTERN));
-                }
-                pattern_map.insert(bytes, i);
-            } else {
-                if wildcard_idx.is_some() {
-                    return Err(Error::new(pat.span(), ERROR_UNREACHABLE_PATTERN));
-                }
-                wildcard_idx.replace(i);
-            }
-        }
-        bound_vals.push(bound_val);
-        bodies.push(*body);
-        i += 1;
-    }
-    let Some(wildcard_idx) = wildcard_idx else {
-        return Err(Error::new(Span::call_site(), ERROR_PATTERN_NOT_COVERED));
-    };
-    Ok(MatchInfo {
-        bodies,
-        pattern_map,
-        wildcard_idx,
-        bound_vals,
-        pat_set,
-    })
-}
+```
+use trie_match::Sparse;
 
-fn trie_match_inner(input: ExprMatch) -> Result<TokenStream, Error> {
-    let ExprMatch {
-        attrs, expr, arms, ..
-    } = input;
-    let MatchInfo {
-        bodies,
-        pattern_map,
-        wildcard_idx,
-        bound_vals,
-        pat_set,
-    } = parse_match_arms(arms)?;
-    let mut trie = Sparse::new();
-    for (k, v) in pattern_map {
-        if v == wildcard_idx {
-            continue;
-        }
-        trie.add(k, v);
-    }
-    let (bases, checks, outs) = trie.build_double_array_trie(wildcard_idx);
+let trie: Sparse<u32> = [("a", 1), ("abc", 2), ("bc", 3)].into_iter().collect();
+let da = trie.build_double_array_trie(0);
 
-    let out_check = outs.iter().zip(checks).map(|(out, check)| {
-        let out = format_ident!("V{out}");
-        quote! { (__TrieMatchValue::#out, #check) }
-    });
-    let arm = bodies
-        .iter()
-        .zip(bound_vals)
-        .enumerate()
-        .map(|(i, (body, bound_val))| {
-            let i = format_ident!("V{i}");
-            let bound_val = bound_val.map_or_else(|| quote! { _ }, |val| quote! { #val });
-            quote! { (__TrieMatchValue::#i, #bound_val ) => #body }
-        });
-    let enumvalue = (0..bodies.len()).map(|i| format_ident!("V{i}"));
-    let wildcard_ident = format_ident!("V{wildcard_idx}");
-    Ok(quote! {
-        {
-            #[derive(Clone, Copy)]
-            enum __TrieMatchValue {
-                #( #enumvalue, )*
-            }
-            #( #attrs )*
-            match #expr {
-                // This is for type inference.
-                query @ ( #( #pat_set | )* _) => {
-                    match (|query| unsafe {
-                        let query_ref = ::core::convert::AsRef::<[u8]>::as_ref(&query);
-                        let bases: &'static [i32] = &[ #( #bases, )* ];
-                        let out_checks: &'static [(__TrieMatchValue, u8)] = &[ #( #out_check, )* ];
-                        let mut pos = 0;
-                        let mut base = bases[0];
-                        for &b in query_ref {
-                            pos = base.wrapping_add(i32::from(b)) as usize;
-                            if let Some((_, check)) = out_checks.get(pos) {
-                                if *check == b {
-                                    base = *bases.get_unchecked(pos);
-                                    continue;
-                                }
-                            }
-                            return (__TrieMatchValue::#wildcard_ident, query);
-                        }
-                        (out_checks.get_unchecked(pos).0, query)
-                    })(query) {
-                        #( #arm, )*
-                    }
-                }
-            }
-        }
-    })
-}
+assert_eq!(*da.get(b"abc"), 2);
+assert_eq!(*da.get(b"ab"), 0);
+```
+"#
+)]
+#![no_std]
 
-/// Generates a match expression that uses a trie structure.
-///
-/// # Examples
-///
-/// ```
-/// use trie_match::trie_match;
-///
-/// let x = "abd";
-///
-/// let result = trie_match! {
-///     match x {
-///         "a" => 0,
-///         "abc" => 1,
-///         pat @ ("abd" | "bcde") => pat.len(),
-///         "bc" => 3,
-///         _ => 4,
-///     }
-/// };
-///
-/// assert_eq!(result, 3);
-/// ```
-#[proc_macro]
-pub fn trie_match(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = parse_macro_input!(input as ExprMatch);
-    trie_match_inner(input)
-        .unwrap_or_else(Error::into_compile_error)
-        .into()
-}
+#[cfg(feature = "alloc")]
+pub use trie_match_core::{
+    BuildOptions, CommonPrefixSearch, DoubleArray, DynamicDoubleArray, LayoutStats,
+    MinimizationStats, Placement, Sparse,
+};
+pub use trie_match_core::{DeserializeError, DoubleArrayRef, Endianness};
+pub use trie_match_macros::*;
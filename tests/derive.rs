@@ -0,0 +1,210 @@
+use trie_match::{TrieAsStr, TrieFromStr};
+
+#[test]
+fn test_from_str() {
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    enum Color {
+        Red,
+        Green,
+        Blue,
+    }
+
+    assert_eq!("Red".parse(), Ok(Color::Red));
+    assert_eq!("Green".parse(), Ok(Color::Green));
+    assert_eq!("Blue".parse(), Ok(Color::Blue));
+    assert_eq!("red".parse::<Color>(), Err(()));
+    assert_eq!("".parse::<Color>(), Err(()));
+}
+
+#[test]
+fn test_from_str_rename_all() {
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "lowercase")]
+    enum Lower {
+        FooBar,
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "UPPERCASE")]
+    enum Upper {
+        FooBar,
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "PascalCase")]
+    enum Pascal {
+        FooBar,
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "camelCase")]
+    enum Camel {
+        FooBar,
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "snake_case")]
+    enum Snake {
+        FooBar,
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum ScreamingSnake {
+        FooBar,
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "kebab-case")]
+    enum Kebab {
+        FooBar,
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "SCREAMING-KEBAB-CASE")]
+    enum ScreamingKebab {
+        FooBar,
+    }
+
+    assert_eq!("foobar".parse(), Ok(Lower::FooBar));
+    assert_eq!("FOOBAR".parse(), Ok(Upper::FooBar));
+    assert_eq!("FooBar".parse(), Ok(Pascal::FooBar));
+    assert_eq!("fooBar".parse(), Ok(Camel::FooBar));
+    assert_eq!("foo_bar".parse(), Ok(Snake::FooBar));
+    assert_eq!("FOO_BAR".parse(), Ok(ScreamingSnake::FooBar));
+    assert_eq!("foo-bar".parse(), Ok(Kebab::FooBar));
+    assert_eq!("FOO-BAR".parse(), Ok(ScreamingKebab::FooBar));
+    assert_eq!("FooBar".parse::<Kebab>(), Err(()));
+}
+
+#[test]
+fn test_from_str_rename_and_alias() {
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(rename_all = "snake_case")]
+    enum Level {
+        #[trie(rename = "warn", alias = "warning", alias = "w")]
+        Warning,
+        #[trie(alias = "err")]
+        #[trie(alias = "e")]
+        Error,
+        DebugInfo,
+    }
+
+    assert_eq!("warn".parse(), Ok(Level::Warning));
+    assert_eq!("warning".parse(), Ok(Level::Warning));
+    assert_eq!("w".parse(), Ok(Level::Warning));
+    assert_eq!("error".parse(), Ok(Level::Error));
+    assert_eq!("err".parse(), Ok(Level::Error));
+    assert_eq!("e".parse(), Ok(Level::Error));
+    assert_eq!("debug_info".parse(), Ok(Level::DebugInfo));
+    assert_eq!("Warning".parse::<Level>(), Err(()));
+}
+
+#[test]
+fn test_from_str_other() {
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    enum Field {
+        Name,
+        #[trie(other)]
+        Other(String),
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    enum Unit {
+        Name,
+        #[trie(other)]
+        Unknown,
+    }
+
+    assert_eq!("Name".parse(), Ok(Field::Name));
+    assert_eq!("Age".parse(), Ok(Field::Other("Age".to_string())));
+    assert_eq!("Name".parse(), Ok(Unit::Name));
+    assert_eq!("Age".parse(), Ok(Unit::Unknown));
+}
+
+#[test]
+fn test_from_str_error() {
+    #[derive(Debug, Default, PartialEq)]
+    struct ParseError;
+
+    #[derive(Debug, PartialEq)]
+    struct ParseErrorWithInput(String);
+
+    fn make_error(s: &str) -> ParseErrorWithInput {
+        ParseErrorWithInput(s.to_string())
+    }
+
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(error = ParseError)]
+    enum A {
+        Foo,
+    }
+    #[derive(Debug, PartialEq, TrieFromStr)]
+    #[trie(error = ParseErrorWithInput, error_fn = make_error)]
+    enum B {
+        Foo,
+    }
+
+    assert_eq!("Foo".parse(), Ok(A::Foo));
+    assert_eq!("Bar".parse::<A>(), Err(ParseError));
+    assert_eq!("Foo".parse(), Ok(B::Foo));
+    assert_eq!(
+        "Bar".parse::<B>(),
+        Err(ParseErrorWithInput("Bar".to_string()))
+    );
+}
+
+#[test]
+fn test_as_str() {
+    #[derive(Debug, PartialEq, TrieAsStr, TrieFromStr)]
+    #[trie(rename_all = "kebab-case")]
+    enum Command {
+        #[trie(alias = "ci")]
+        Commit,
+        CherryPick,
+        #[trie(rename = "rm", alias = "remove")]
+        Remove,
+    }
+
+    const COMMIT: &str = Command::Commit.as_str();
+
+    assert_eq!(COMMIT, "commit");
+    assert_eq!(Command::CherryPick.as_str(), "cherry-pick");
+    assert_eq!(Command::Remove.as_str(), "rm");
+    assert_eq!(Command::Remove.to_string(), "rm");
+    assert_eq!(format!("{}", Command::CherryPick), "cherry-pick");
+    for command in [Command::Commit, Command::CherryPick, Command::Remove] {
+        assert_eq!(command.as_str().parse(), Ok(command));
+    }
+}
+
+#[test]
+fn test_as_str_other() {
+    #[derive(Debug, PartialEq, TrieAsStr, TrieFromStr)]
+    enum Field {
+        Name,
+        #[trie(other)]
+        Other(String),
+    }
+    #[derive(Debug, PartialEq, TrieAsStr, TrieFromStr)]
+    #[trie(rename_all = "lowercase")]
+    enum Unit {
+        Name,
+        #[trie(other)]
+        Unknown,
+    }
+
+    assert_eq!(Field::Name.as_str(), "Name");
+    assert_eq!(Field::Other("Age".to_string()).as_str(), "Age");
+    assert_eq!(Field::Other("Age".to_string()).to_string(), "Age");
+    assert_eq!(Unit::Name.as_str(), "name");
+    assert_eq!(Unit::Unknown.as_str(), "unknown");
+    assert_eq!(Unit::Unknown.as_str().parse(), Ok(Unit::Unknown));
+}
+
+#[test]
+fn test_as_str_other_field_name_unused() {
+    #[derive(Debug, PartialEq, TrieAsStr, TrieFromStr)]
+    enum Token {
+        #[trie(rename = "Unknown")]
+        Literal,
+        #[trie(other)]
+        Unknown(String),
+    }
+
+    assert_eq!(Token::Literal.as_str(), "Unknown");
+    assert_eq!("Unknown".parse(), Ok(Token::Literal));
+    assert_eq!("Name".parse(), Ok(Token::Unknown("Name".to_string())));
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "serde")]
+
+use serde::de::{
+    value::{BytesDeserializer, Error},
+    IntoDeserializer,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use trie_match::TrieDeserializeIdentifier;
+
+#[derive(Debug, PartialEq, TrieDeserializeIdentifier)]
+#[trie(rename_all = "snake_case")]
+enum Field {
+    UserName,
+    #[trie(rename = "mail", alias = "email")]
+    Email,
+}
+
+#[derive(Debug, PartialEq, TrieDeserializeIdentifier)]
+#[trie(variant_identifier)]
+enum Variant {
+    Foo,
+    Bar,
+}
+
+#[derive(Debug, PartialEq, TrieDeserializeIdentifier)]
+enum FieldOrOther {
+    Foo,
+    #[trie(other)]
+    Other(String),
+}
+
+#[derive(Debug, PartialEq, TrieDeserializeIdentifier)]
+enum FieldOrIgnore {
+    Foo,
+    #[trie(other)]
+    Ignore,
+}
+
+fn from_str<'de, T: Deserialize<'de>>(s: &'de str) -> Result<T, Error> {
+    T::deserialize(s.into_deserializer())
+}
+
+fn from_bytes<'de, T: Deserialize<'de>>(b: &'de [u8]) -> Result<T, Error> {
+    T::deserialize(BytesDeserializer::new(b))
+}
+
+fn from_u64<'de, T: Deserialize<'de>>(v: u64) -> Result<T, Error> {
+    T::deserialize(v.into_deserializer())
+}
+
+#[test]
+fn test_field_identifier() {
+    assert_eq!(from_str("user_name"), Ok(Field::UserName));
+    assert_eq!(from_str("mail"), Ok(Field::Email));
+    assert_eq!(from_str("email"), Ok(Field::Email));
+    assert_eq!(from_bytes(b"user_name"), Ok(Field::UserName));
+    assert_eq!(from_bytes(b"email"), Ok(Field::Email));
+    assert_eq!(from_u64(0), Ok(Field::UserName));
+    assert_eq!(from_u64(1), Ok(Field::Email));
+    assert_eq!(
+        from_str::<Field>("age").unwrap_err().to_string(),
+        "unknown field `age`, expected `user_name` or `mail`",
+    );
+    assert_eq!(
+        from_bytes::<Field>(b"age").unwrap_err().to_string(),
+        "unknown field `age`, expected `user_name` or `mail`",
+    );
+    assert!(from_bytes::<Field>(b"\xff").is_err());
+    assert!(from_u64::<Field>(2).is_err());
+}
+
+#[test]
+fn test_variant_identifier() {
+    assert_eq!(from_str("Foo"), Ok(Variant::Foo));
+    assert_eq!(from_str("Bar"), Ok(Variant::Bar));
+    assert_eq!(
+        from_str::<Variant>("Baz").unwrap_err().to_string(),
+        "unknown variant `Baz`, expected `Foo` or `Bar`",
+    );
+}
+
+#[test]
+fn test_other() {
+    assert_eq!(from_str("Foo"), Ok(FieldOrOther::Foo));
+    assert_eq!(from_str("Bar"), Ok(FieldOrOther::Other("Bar".to_string())));
+    assert_eq!(
+        from_bytes(b"Bar"),
+        Ok(FieldOrOther::Other("Bar".to_string()))
+    );
+    assert!(from_u64::<FieldOrOther>(1).is_err());
+    assert_eq!(from_str("Foo"), Ok(FieldOrIgnore::Foo));
+    assert_eq!(from_str("Bar"), Ok(FieldOrIgnore::Ignore));
+    assert_eq!(from_u64(1), Ok(FieldOrIgnore::Ignore));
+}
+
+#[test]
+fn test_struct() {
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    impl<'de> Deserialize<'de> for Point {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            #[derive(TrieDeserializeIdentifier)]
+            #[trie(rename_all = "lowercase")]
+            enum PointField {
+                X,
+                Y,
+                #[trie(other)]
+                Ignore,
+            }
+
+            struct Visitor;
+
+            impl<'de> serde::de::Visitor<'de> for Visitor {
+                type Value = Point;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("struct Point")
+                }
+
+                fn visit_map<A: serde::de::MapAccess<'de>>(
+                    self,
+                    mut map: A,
+                ) -> Result<Point, A::Error> {
+                    let mut x = None;
+                    let mut y = None;
+                    while let Some(key) = map.next_key()? {
+                        match key {
+                            PointField::X => x = Some(map.next_value()?),
+                            PointField::Y => y = Some(map.next_value()?),
+                            PointField::Ignore => {
+                                map.next_value::<serde::de::IgnoredAny>()?;
+                            }
+                        }
+                    }
+                    Ok(Point {
+                        x: x.ok_or_else(|| serde::de::Error::missing_field("x"))?,
+                        y: y.ok_or_else(|| serde::de::Error::missing_field("y"))?,
+                    })
+                }
+            }
+
+            deserializer.deserialize_map(Visitor)
+        }
+    }
+
+    let map: BTreeMap<&str, u32> = [("x", 1), ("z", 2), ("y", 3)].into_iter().collect();
+    let point = Point::deserialize(map.into_deserializer());
+    assert_eq!(point, Ok::<_, Error>(Point { x: 1, y: 3 }));
+}
@@ -1,6 +1,6 @@
 #![no_std]
 
-use trie_match::trie_match;
+use trie_match::{trie_match, trie_match_all, trie_match_fn, trie_set};
 
 #[test]
 fn test_only_wildcard() {
@@ -21,6 +21,7 @@ fn test_prefix_patterns() {
     // 0 -a-> 1 -b-> 2 -c-> * -d-> 3
     let f = |text| {
         trie_match! {
+            #[strategy(double_array)]
             match text {
                 "" => 0,
                 "a" => 1,
@@ -43,6 +44,7 @@ fn test_longer_query() {
     // * -a-> * -b-> 0
     let f = |text| {
         trie_match! {
+            #[strategy(double_array)]
             match text {
                 "ab" => 0,
                 _ => 1,
@@ -60,6 +62,7 @@ fn test_branch_root() {
     //   \-b-> 1
     let f = |text| {
         trie_match! {
+            #[strategy(double_array)]
             match text {
                 "a" => 0,
                 "b" => 1,
@@ -81,6 +84,7 @@ fn test_branch_multiple_times() {
     //   \-b-> 2  \-c-> 3  \-d-> * --e--> 4
     let f = |text| {
         trie_match! {
+            #[strategy(double_array)]
             match text {
                 "a" => 0,
                 "abcd" => 1,
@@ -115,6 +119,7 @@ fn test_branch_multiple_times() {
 fn test_try_base_conflict() {
     let f = |text| {
         trie_match! {
+            #[strategy(double_array)]
             match text {
                 // The following pattern adds multiple zeros into a base array in a normal
                 // double-array, but it is not allowed in a compact double-array.
@@ -138,6 +143,7 @@ fn test_invalid_root_check_of_zero() {
     // check: [0,   1]
     let f = |text| {
         trie_match! {
+            #[strategy(double_array)]
             match text {
                 "\u{1}" => 1,
                 _ => 0,
@@ -147,6 +153,615 @@ fn test_invalid_root_check_of_zero() {
     assert_eq!(f("\u{0}\u{1}"), 0);
 }
 
+#[test]
+fn test_tail_suffixes() {
+    fn f(text: &str) -> (usize, &'static str) {
+        trie_match! {
+            #[strategy(double_array)]
+            match text {
+                #[literal(pat)]
+                "abcdefgh" => (0, pat),
+                #[literal(pat)]
+                "abcxyz" | "international" => (1, pat),
+                #[literal(pat)]
+                "abc" | "internet" => (2, pat),
+                _ => (3, ""),
+            }
+        }
+    }
+    assert_eq!(f("abcdefgh"), (0, "abcdefgh"));
+    assert_eq!(f("abcxyz"), (1, "abcxyz"));
+    assert_eq!(f("international"), (1, "international"));
+    assert_eq!(f("abc"), (2, "abc"));
+    assert_eq!(f("internet"), (2, "internet"));
+    assert_eq!(f("abcd"), (3, ""));
+    assert_eq!(f("abcdefg"), (3, ""));
+    assert_eq!(f("abcdefghi"), (3, ""));
+    assert_eq!(f("abcdefgx"), (3, ""));
+    assert_eq!(f("internation"), (3, ""));
+    assert_eq!(f("inter"), (3, ""));
+    assert_eq!(f(""), (3, ""));
+}
+
+#[test]
+fn test_alphabet_remapping() {
+    fn f(text: &str) -> (usize, &'static str) {
+        trie_match! {
+            #[alphabet_remapping]
+            match text {
+                #[literal(pat)]
+                "\u{65e5}\u{672c}" | "\u{65e5}\u{672c}\u{8a9e}" => (0, pat),
+                #[literal(pat)]
+                "abc" | "abd" | "international" => (1, pat),
+                _ => (2, ""),
+            }
+        }
+    }
+    assert_eq!(f("\u{65e5}\u{672c}"), (0, "\u{65e5}\u{672c}"));
+    assert_eq!(
+        f("\u{65e5}\u{672c}\u{8a9e}"),
+        (0, "\u{65e5}\u{672c}\u{8a9e}")
+    );
+    assert_eq!(f("abd"), (1, "abd"));
+    assert_eq!(f("international"), (1, "international"));
+    assert_eq!(f("\u{65e5}"), (2, ""));
+    assert_eq!(f("ab"), (2, ""));
+    assert_eq!(f("abz"), (2, ""));
+    assert_eq!(f("\0"), (2, ""));
+    assert_eq!(f(""), (2, ""));
+}
+
+#[test]
+fn test_minimize() {
+    fn f(text: &str) -> (usize, &'static str) {
+        trie_match! {
+            #[minimize]
+            match text {
+                "walk" | "talk" | "jump" => (0, ""),
+                "walks" | "talks" | "jumps" => (1, ""),
+                "walked" | "talked" | "jumped" => (2, ""),
+                #[literal(pat)]
+                "walking" | "talking" | "jumping" => (3, pat),
+                _ => (4, ""),
+            }
+        }
+    }
+    const fn g(text: &[u8]) -> usize {
+        trie_match! {
+            #[const_compatible]
+            #[minimize]
+            match text {
+                b"walk" | b"talk" => 0,
+                b"walks" | b"talks" => 1,
+                _ => 2,
+            }
+        }
+    }
+    assert_eq!(f("talks"), (1, ""));
+    assert_eq!(f("jumped"), (2, ""));
+    assert_eq!(f("walking"), (3, "walking"));
+    assert_eq!(f("talking"), (3, "talking"));
+    assert_eq!(f("talke"), (4, ""));
+    assert_eq!(f("jumpss"), (4, ""));
+    assert_eq!(f("alk"), (4, ""));
+    assert_eq!(g(b"talks"), 1);
+    assert_eq!(g(b"walk"), 0);
+    assert_eq!(g(b"wal"), 2);
+}
+
+#[test]
+fn test_long_unbranched_paths() {
+    fn f(text: &str) -> (usize, &'static str) {
+        trie_match! {
+            #[strategy(double_array)]
+            match text {
+                "application/json" | "application/xml" => (0, ""),
+                #[literal(pat)]
+                "application/vnd.ms-excel" | "application/vnd.ms-powerpoint" => (1, pat),
+                "content-length" | "content-type" => (2, ""),
+                "text" => (3, ""),
+                "text/html" | "text/plain" => (4, ""),
+                _ => (5, ""),
+            }
+        }
+    }
+    const fn g(text: &[u8]) -> usize {
+        trie_match! {
+            #[const_compatible]
+            #[strategy(double_array)]
+            match text {
+                b"application/json" | b"application/xml" => 0,
+                b"content-length" | b"content-type" => 1,
+                b"\xffunbranched\xfe" | b"\xffunbranched\xfd" => 2,
+                _ => 3,
+            }
+        }
+    }
+    assert_eq!(f("application/json"), (0, ""));
+    assert_eq!(f("application/xml"), (0, ""));
+    assert_eq!(
+        f("application/vnd.ms-excel"),
+        (1, "application/vnd.ms-excel")
+    );
+    assert_eq!(
+        f("application/vnd.ms-powerpoint"),
+        (1, "application/vnd.ms-powerpoint")
+    );
+    assert_eq!(f("content-length"), (2, ""));
+    assert_eq!(f("content-type"), (2, ""));
+    assert_eq!(f("text"), (3, ""));
+    assert_eq!(f("text/html"), (4, ""));
+    assert_eq!(f("text/plain"), (4, ""));
+    assert_eq!(f("application/"), (5, ""));
+    assert_eq!(f("applicatio"), (5, ""));
+    assert_eq!(f("applicationxjson"), (5, ""));
+    assert_eq!(f("application/vnd.ms-"), (5, ""));
+    assert_eq!(f("application/vnd.ms-excels"), (5, ""));
+    assert_eq!(f("content-lengt"), (5, ""));
+    assert_eq!(f("text/"), (5, ""));
+    assert_eq!(g(b"application/json"), 0);
+    assert_eq!(g(b"application/xml"), 0);
+    assert_eq!(g(b"content-type"), 1);
+    assert_eq!(g(b"\xffunbranched\xfd"), 2);
+    assert_eq!(g(b"\xffunbranches\xfd"), 3);
+    assert_eq!(g(b"applications/json"), 3);
+    assert_eq!(g(b"content"), 3);
+    const _: () = assert!(g(b"content-length") == 1);
+}
+
+#[test]
+fn test_padded_slots() {
+    fn f(text: &[u8]) -> usize {
+        trie_match! {
+            #[strategy(double_array)]
+            #[alphabet_remapping]
+            match text {
+                b"aa" | b"ab" | b"ac" | b"ad" => 0,
+                b"ba" | b"bb" | b"bc" | b"bd" => 1,
+                b"ca" | b"cb" | b"cc" | b"cd" => 2,
+                b"da" | b"db" | b"dc" | b"dd" => 3,
+                _ => 4,
+            }
+        }
+    }
+    for (i, x) in (b'a'..=b'd').enumerate() {
+        for y in b'a'..=b'd' {
+            assert_eq!(f(&[x, y]), i);
+            assert_eq!(f(&[x, y, y]), 4);
+            assert_eq!(f(&[x, y, 0xff]), 4);
+        }
+        assert_eq!(f(&[x]), 4);
+        assert_eq!(f(&[x, b'e']), 4);
+        assert_eq!(f(&[x, 0]), 4);
+        assert_eq!(f(&[x, 0xff]), 4);
+        assert_eq!(f(&[0xff, x]), 4);
+    }
+    assert_eq!(f(b""), 4);
+    assert_eq!(f(b"\xff"), 4);
+}
+
+#[test]
+fn test_padded_slots_unused_root_check() {
+    fn f(query: &[u8]) -> usize {
+        trie_match! {
+            #[strategy(double_array)]
+            match query {
+                b"\x1a\xfe\x81\x80\xc5\xc9\x36\xf0\x25\x8b\xac" => 0,
+                b"\xf0\x29\x67\x77" => 1,
+                _ => 9,
+            }
+        }
+    }
+    assert_eq!(f(b"\x1a\xfe\x81\x80\xc5\xc9\x36\xf0\x25\x8b\xac"), 0);
+    assert_eq!(f(b"\xf0\x29\x67\x77"), 1);
+    assert_eq!(f(b"\x19\xf0\x29\x67\x77"), 9);
+    assert_eq!(f(b"\x00\xf0\x29\x67\x77"), 9);
+    assert_eq!(f(b"\xf0\x29\x67"), 9);
+}
+
+/// Defines a function with `trie_match!` and the same function with a plain `match`, which
+/// return the matched pattern.
+macro_rules! define_match_pair {
+    ($f:ident, $g:ident, [$($pat:tt,)*]) => {
+        fn $f(query: &[u8]) -> Option<&'static [u8]> {
+            trie_match! {
+                #[strategy(double_array)]
+                match query {
+                    $( $pat => Some(&$pat[..]), )*
+                    _ => None,
+                }
+            }
+        }
+
+        fn $g(query: &[u8]) -> Option<&'static [u8]> {
+            match query {
+                $( $pat => Some(&$pat[..]), )*
+                _ => None,
+            }
+        }
+    };
+}
+
+// All strings of up to 4 bytes of `\x00`, `\x80`, and `\xff` fill the double-array densely
+// enough to be padded, and all 256 bytes are used as codes. The two longer patterns add a tail
+// and a segment.
+define_match_pair!(
+    padded_trie_match,
+    padded_plain_match,
+    [
+        b"\x00",
+        b"\x80",
+        b"\xff",
+        b"\x00\x00",
+        b"\x00\x80",
+        b"\x00\xff",
+        b"\x80\x00",
+        b"\x80\x80",
+        b"\x80\xff",
+        b"\xff\x00",
+        b"\xff\x80",
+        b"\xff\xff",
+        b"\x00\x00\x00",
+        b"\x00\x00\x80",
+        b"\x00\x00\xff",
+        b"\x00\x80\x00",
+        b"\x00\x80\x80",
+        b"\x00\x80\xff",
+        b"\x00\xff\x00",
+        b"\x00\xff\x80",
+        b"\x00\xff\xff",
+        b"\x80\x00\x00",
+        b"\x80\x00\x80",
+        b"\x80\x00\xff",
+        b"\x80\x80\x00",
+        b"\x80\x80\x80",
+        b"\x80\x80\xff",
+        b"\x80\xff\x00",
+        b"\x80\xff\x80",
+        b"\x80\xff\xff",
+        b"\xff\x00\x00",
+        b"\xff\x00\x80",
+        b"\xff\x00\xff",
+        b"\xff\x80\x00",
+        b"\xff\x80\x80",
+        b"\xff\x80\xff",
+        b"\xff\xff\x00",
+        b"\xff\xff\x80",
+        b"\xff\xff\xff",
+        b"\x00\x00\x00\x00",
+        b"\x00\x00\x00\x80",
+        b"\x00\x00\x00\xff",
+        b"\x00\x00\x80\x00",
+        b"\x00\x00\x80\x80",
+        b"\x00\x00\x80\xff",
+        b"\x00\x00\xff\x00",
+        b"\x00\x00\xff\x80",
+        b"\x00\x00\xff\xff",
+        b"\x00\x80\x00\x00",
+        b"\x00\x80\x00\x80",
+        b"\x00\x80\x00\xff",
+        b"\x00\x80\x80\x00",
+        b"\x00\x80\x80\x80",
+        b"\x00\x80\x80\xff",
+        b"\x00\x80\xff\x00",
+        b"\x00\x80\xff\x80",
+        b"\x00\x80\xff\xff",
+        b"\x00\xff\x00\x00",
+        b"\x00\xff\x00\x80",
+        b"\x00\xff\x00\xff",
+        b"\x00\xff\x80\x00",
+        b"\x00\xff\x80\x80",
+        b"\x00\xff\x80\xff",
+        b"\x00\xff\xff\x00",
+        b"\x00\xff\xff\x80",
+        b"\x00\xff\xff\xff",
+        b"\x80\x00\x00\x00",
+        b"\x80\x00\x00\x80",
+        b"\x80\x00\x00\xff",
+        b"\x80\x00\x80\x00",
+        b"\x80\x00\x80\x80",
+        b"\x80\x00\x80\xff",
+        b"\x80\x00\xff\x00",
+        b"\x80\x00\xff\x80",
+        b"\x80\x00\xff\xff",
+        b"\x80\x80\x00\x00",
+        b"\x80\x80\x00\x80",
+        b"\x80\x80\x00\xff",
+        b"\x80\x80\x80\x00",
+        b"\x80\x80\x80\x80",
+        b"\x80\x80\x80\xff",
+        b"\x80\x80\xff\x00",
+        b"\x80\x80\xff\x80",
+        b"\x80\x80\xff\xff",
+        b"\x80\xff\x00\x00",
+        b"\x80\xff\x00\x80",
+        b"\x80\xff\x00\xff",
+        b"\x80\xff\x80\x00",
+        b"\x80\xff\x80\x80",
+        b"\x80\xff\x80\xff",
+        b"\x80\xff\xff\x00",
+        b"\x80\xff\xff\x80",
+        b"\x80\xff\xff\xff",
+        b"\xff\x00\x00\x00",
+        b"\xff\x00\x00\x80",
+        b"\xff\x00\x00\xff",
+        b"\xff\x00\x80\x00",
+        b"\xff\x00\x80\x80",
+        b"\xff\x00\x80\xff",
+        b"\xff\x00\xff\x00",
+        b"\xff\x00\xff\x80",
+        b"\xff\x00\xff\xff",
+        b"\xff\x80\x00\x00",
+        b"\xff\x80\x00\x80",
+        b"\xff\x80\x00\xff",
+        b"\xff\x80\x80\x00",
+        b"\xff\x80\x80\x80",
+        b"\xff\x80\x80\xff",
+        b"\xff\x80\xff\x00",
+        b"\xff\x80\xff\x80",
+        b"\xff\x80\xff\xff",
+        b"\xff\xff\x00\x00",
+        b"\xff\xff\x00\x80",
+        b"\xff\xff\x00\xff",
+        b"\xff\xff\x80\x00",
+        b"\xff\xff\x80\x80",
+        b"\xff\xff\x80\xff",
+        b"\xff\xff\xff\x00",
+        b"\xff\xff\xff\x80",
+        b"\xff\xff\xff\xff",
+        b"\x00\x00\x00\x00\x01\x01\x01\x01\x01\x01\x01\x01\x01",
+        b"\x00\x00\x00\x00\x01\x01\x01\x01\x01\x01\x01\x01\x02",
+    ]
+);
+
+#[test]
+fn test_padded_slots_random() {
+    const BYTES: [u8; 8] = [0x00, 0x01, 0x02, 0x7f, 0x80, 0xfe, 0xff, 0x1a];
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as usize
+    };
+    for _ in 0..100_000 {
+        let mut query = [0; 16];
+        let len = next() % query.len();
+        for b in &mut query[..len] {
+            // Most bytes are in the patterns, so that queries often go deep into the trie.
+            *b = if next() % 8 == 0 {
+                next() as u8
+            } else {
+                BYTES[next() % BYTES.len()]
+            };
+        }
+        let query = &query[..len];
+        assert_eq!(
+            padded_trie_match(query),
+            padded_plain_match(query),
+            "{query:?}"
+        );
+    }
+}
+
+#[test]
+fn test_strategy_length() {
+    fn f(text: &str) -> (usize, &'static str) {
+        trie_match! {
+            #[strategy(length)]
+            match text {
+                "a" | "abbr" => (0, ""),
+                #[literal(pat)]
+                "div" | "span" | "dim" => (1, pat),
+                "blockquote" => (2, ""),
+                "" => (3, ""),
+                _ => (4, ""),
+            }
+        }
+    }
+    const fn g(text: &[u8]) -> usize {
+        trie_match! {
+            #[const_compatible]
+            #[strategy(length)]
+            match text {
+                b"a" => 0,
+                b"abc" | b"\xff\xfe\xfd" => 1,
+                _ => 2,
+            }
+        }
+    }
+    assert_eq!(f("a"), (0, ""));
+    assert_eq!(f("abbr"), (0, ""));
+    assert_eq!(f("div"), (1, "div"));
+    assert_eq!(f("dim"), (1, "dim"));
+    assert_eq!(f("span"), (1, "span"));
+    assert_eq!(f("blockquote"), (2, ""));
+    assert_eq!(f(""), (3, ""));
+    assert_eq!(f("dig"), (4, ""));
+    assert_eq!(f("spa"), (4, ""));
+    assert_eq!(f("blockquotes"), (4, ""));
+    assert_eq!(g(b"\xff\xfe\xfd"), 1);
+    assert_eq!(g(b"abc"), 1);
+    assert_eq!(g(b"ab"), 2);
+    const _: () = assert!(g(b"a") == 0);
+}
+
+#[test]
+fn test_strategy_decision_tree() {
+    fn f(text: &str) -> (usize, &'static str) {
+        trie_match! {
+            #[strategy(decision_tree)]
+            match text {
+                "a" | "abc" => (0, ""),
+                #[literal(pat)]
+                "ab" | "abd" | "bcd" => (1, pat),
+                "\u{3042}" => (2, ""),
+                "" => (3, ""),
+                _ => (4, ""),
+            }
+        }
+    }
+    const fn g(text: &[u8]) -> usize {
+        trie_match! {
+            #[const_compatible]
+            #[strategy(decision_tree)]
+            match text {
+                b"ab" | b"ba" => 0,
+                b"abc" | b"\xff" => 1,
+                _ => 2,
+            }
+        }
+    }
+    assert_eq!(f("a"), (0, ""));
+    assert_eq!(f("abc"), (0, ""));
+    assert_eq!(f("ab"), (1, "ab"));
+    assert_eq!(f("abd"), (1, "abd"));
+    assert_eq!(f("bcd"), (1, "bcd"));
+    assert_eq!(f("\u{3042}"), (2, ""));
+    assert_eq!(f(""), (3, ""));
+    assert_eq!(f("b"), (4, ""));
+    assert_eq!(f("abe"), (4, ""));
+    assert_eq!(f("bbd"), (4, ""));
+    assert_eq!(f("abcd"), (4, ""));
+    assert_eq!(g(b"ba"), 0);
+    assert_eq!(g(b"\xff"), 1);
+    assert_eq!(g(b"\xff\xff"), 2);
+    const _: () = assert!(g(b"abc") == 1);
+}
+
+#[test]
+fn test_strategy_match() {
+    fn f(text: &[u8]) -> (usize, &'static [u8]) {
+        trie_match! {
+            #[strategy(match)]
+            match text {
+                b"a" | b"ab" => (0, b""),
+                #[literal(pat)]
+                b"abc" | [0, 255] => (1, pat),
+                _ => (2, b""),
+            }
+        }
+    }
+    const fn g(text: &str) -> usize {
+        trie_match! {
+            #[const_compatible]
+            #[strategy(match)]
+            match text {
+                "a" => 0,
+                pat @ ("bc" | "bcd") => pat.len(),
+                _ => 9,
+            }
+        }
+    }
+    assert_eq!(f(b"a"), (0, &b""[..]));
+    assert_eq!(f(b"ab"), (0, &b""[..]));
+    assert_eq!(f(b"abc"), (1, &b"abc"[..]));
+    assert_eq!(f(&[0, 255]), (1, &[0, 255][..]));
+    assert_eq!(f(b"abcd"), (2, &b""[..]));
+    assert_eq!(g("a"), 0);
+    assert_eq!(g("bcd"), 3);
+    assert_eq!(g("b"), 9);
+    const _: () = assert!(g("bc") == 2);
+}
+
+#[test]
+fn test_strategy_perfect_hash() {
+    fn f(text: &[u8]) -> (usize, &'static [u8]) {
+        trie_match! {
+            #[strategy(perfect_hash)]
+            match text {
+                b"" | b"a" | b"ab" => (0, b""),
+                #[literal(pat)]
+                b"abcdefgh" | b"abcdefghijklmnopq" | [0, 255] => (1, pat),
+                _ => (2, b""),
+            }
+        }
+    }
+    const fn g(text: &str) -> usize {
+        trie_match! {
+            #[const_compatible]
+            #[strategy(perfect_hash)]
+            match text {
+                "application/octet-stream" => 0,
+                pat @ ("application/json" | "text/html") => pat.len(),
+                _ => 99,
+            }
+        }
+    }
+    assert_eq!(f(b""), (0, &b""[..]));
+    assert_eq!(f(b"a"), (0, &b""[..]));
+    assert_eq!(f(b"ab"), (0, &b""[..]));
+    assert_eq!(f(b"abcdefgh"), (1, &b"abcdefgh"[..]));
+    assert_eq!(f(b"abcdefghijklmnopq"), (1, &b"abcdefghijklmnopq"[..]));
+    assert_eq!(f(&[0, 255]), (1, &[0, 255][..]));
+    assert_eq!(f(b"abcdefghi"), (2, &b""[..]));
+    assert_eq!(f(b"abcdefghijklmnopr"), (2, &b""[..]));
+    assert_eq!(f(b"b"), (2, &b""[..]));
+    assert_eq!(g("application/octet-stream"), 0);
+    assert_eq!(g("application/json"), 16);
+    assert_eq!(g("application/jsonx"), 99);
+    assert_eq!(g("text/htm"), 99);
+    const _: () = assert!(g("text/html") == 9);
+}
+
+#[test]
+fn test_strategy_perfect_hash_only_wildcard() {
+    fn f(text: &str) -> usize {
+        trie_match! {
+            #[strategy(perfect_hash)]
+            match text {
+                _ => 1,
+            }
+        }
+    }
+    assert_eq!(f(""), 1);
+    assert_eq!(f("a"), 1);
+}
+
+#[test]
+fn test_many_positions() {
+    // The double-array has more than 127 positions, so base values are `i16`.
+    fn f(text: &str) -> usize {
+        trie_match! {
+            #[strategy(double_array)]
+            match text {
+                "000" | "001" | "002" | "003" | "004" | "005" | "006" | "007" | "008" | "009" => 0,
+                "010" | "011" | "012" | "013" | "014" | "015" | "016" | "017" | "018" | "019" => 1,
+                "020" | "021" | "022" | "023" | "024" | "025" | "026" | "027" | "028" | "029" => 2,
+                "030" | "031" | "032" | "033" | "034" | "035" | "036" | "037" | "038" | "039" => 3,
+                "040" | "041" | "042" | "043" | "044" | "045" | "046" | "047" | "048" | "049" => 4,
+                "050" | "051" | "052" | "053" | "054" | "055" | "056" | "057" | "058" | "059" => 5,
+                "060" | "061" | "062" | "063" | "064" | "065" | "066" | "067" | "068" | "069" => 6,
+                "070" | "071" | "072" | "073" | "074" | "075" | "076" | "077" | "078" | "079" => 7,
+                "080" | "081" | "082" | "083" | "084" | "085" | "086" | "087" | "088" | "089" => 8,
+                "090" | "091" | "092" | "093" | "094" | "095" | "096" | "097" | "098" | "099" => 9,
+                "100" | "101" | "102" | "103" | "104" | "105" | "106" | "107" | "108" | "109" => 10,
+                "110" | "111" | "112" | "113" | "114" | "115" | "116" | "117" | "118" | "119" => 11,
+                "120" | "121" | "122" | "123" | "124" | "125" | "126" | "127" | "128" | "129" => 12,
+                "130" | "131" | "132" | "133" | "134" | "135" | "136" | "137" | "138" | "139" => 13,
+                "140" | "141" | "142" | "143" | "144" | "145" | "146" | "147" | "148" | "149" => 14,
+                "150" | "151" | "152" | "153" | "154" | "155" | "156" | "157" | "158" | "159" => 15,
+                "160" | "161" | "162" | "163" | "164" | "165" | "166" | "167" | "168" | "169" => 16,
+                "170" | "171" | "172" | "173" | "174" | "175" | "176" | "177" | "178" | "179" => 17,
+                "180" | "181" | "182" | "183" | "184" | "185" | "186" | "187" | "188" | "189" => 18,
+                "190" | "191" | "192" | "193" | "194" | "195" | "196" | "197" | "198" | "199" => 19,
+                _ => 20,
+            }
+        }
+    }
+    for i in 0..200 {
+        let digits = [
+            b'0' + (i / 100) as u8,
+            b'0' + (i / 10 % 10) as u8,
+            b'0' + (i % 10) as u8,
+        ];
+        assert_eq!(f(core::str::from_utf8(&digits).unwrap()), i / 10);
+    }
+    assert_eq!(f("200"), 20);
+    assert_eq!(f("19"), 20);
+    assert_eq!(f("0000"), 20);
+}
+
 #[test]
 fn test_bytes_literal() {
     let f = |text: &[u8]| {
@@ -223,6 +838,523 @@ fn test_binds() {
     assert_eq!(f("abcdefg"), "efg");
 }
 
+#[test]
+fn test_literal_str() {
+    fn f(text: &str) -> &'static str {
+        trie_match! {
+            match text {
+                #[literal(pat)]
+                "abc" | "def" => pat,
+                #[literal(pat)]
+                x @ "ghi" => &pat[x.len() - 1..],
+                _ => "",
+            }
+        }
+    }
+    assert_eq!(f("abc"), "abc");
+    assert_eq!(f("def"), "def");
+    assert_eq!(f("ghi"), "i");
+    assert_eq!(f("ab"), "");
+    assert_eq!(f("abcd"), "");
+}
+
+#[test]
+fn test_literal_bytes() {
+    fn f(text: &[u8]) -> &'static [u8] {
+        trie_match! {
+            match text {
+                #[literal(pat)]
+                b"abc" | [b'd', b'e'] => pat,
+                _ => b"",
+            }
+        }
+    }
+    assert_eq!(f(b"abc"), b"abc");
+    assert_eq!(f(b"de"), b"de");
+    assert_eq!(f(b"d"), b"");
+}
+
+#[test]
+fn test_suggest() {
+    fn f(text: &str) -> ([(&'static str, usize); 4], usize) {
+        trie_match! {
+            match text {
+                "--verbose" | "--version" => ([("", 0); 4], 0),
+                "--quiet" => ([("", 0); 4], 0),
+                "-v" => ([("", 0); 4], 0),
+                #[suggest(candidates, distance = 2)]
+                _ => {
+                    let mut result = [("", 0); 4];
+                    let mut len = 0;
+                    for candidate in candidates {
+                        result[len] = candidate;
+                        len += 1;
+                    }
+                    (result, len)
+                }
+            }
+        }
+    }
+    let (result, len) = f("--verbos");
+    assert_eq!(&result[..len], &[("--verbose", 1)]);
+    let (result, len) = f("--versiose");
+    assert_eq!(&result[..len], &[("--verbose", 2), ("--version", 2)]);
+    let (result, len) = f("--quite");
+    assert_eq!(&result[..len], &[("--quiet", 2)]);
+    let (result, len) = f("v");
+    assert_eq!(&result[..len], &[("-v", 1)]);
+    let (result, len) = f("");
+    assert_eq!(&result[..len], &[("-v", 2)]);
+    let (result, len) = f("--help");
+    assert_eq!(&result[..len], &[]);
+    let (result, len) = f("--verbose--verbose");
+    assert_eq!(&result[..len], &[]);
+}
+
+/// Computes the edit distance of two strings of at most 31 bytes.
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut rows = [[0; 32]; 2];
+    for (j, d) in rows[0].iter_mut().enumerate() {
+        *d = j;
+    }
+    for (i, &x) in a.iter().enumerate() {
+        let (prev, cur) = if i % 2 == 0 {
+            let (prev, cur) = rows.split_at_mut(1);
+            (&prev[0], &mut cur[0])
+        } else {
+            let (cur, prev) = rows.split_at_mut(1);
+            (&prev[0], &mut cur[0])
+        };
+        cur[0] = i + 1;
+        for (j, &y) in b.iter().enumerate() {
+            cur[j + 1] = (prev[j + 1] + 1)
+                .min(cur[j] + 1)
+                .min(prev[j] + usize::from(x != y));
+        }
+    }
+    rows[a.len() % 2][b.len()]
+}
+
+#[test]
+fn test_suggest_random() {
+    const PATTERNS: [&str; 6] = [
+        "a",
+        "ab",
+        "abab",
+        "baab",
+        "bbbaaa",
+        "abababababbbbbbaaaaaaababab",
+    ];
+    fn f(text: &str) -> ([(&'static str, usize); 6], usize) {
+        trie_match! {
+            match text {
+                "a" | "ab" | "abab" | "baab" | "bbbaaa" => ([("", 0); 6], 0),
+                "abababababbbbbbaaaaaaababab" => ([("", 0); 6], 0),
+                #[suggest(candidates, distance = 3)]
+                _ => {
+                    let mut result = [("", 0); 6];
+                    let mut len = 0;
+                    for candidate in candidates {
+                        result[len] = candidate;
+                        len += 1;
+                    }
+                    (result, len)
+                }
+            }
+        }
+    }
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as usize
+    };
+    for _ in 0..10_000 {
+        let mut query = [0; 31];
+        // Queries are edits of the patterns, so that they are often within the distance.
+        let pattern = PATTERNS[next() % PATTERNS.len()].as_bytes();
+        let mut len = 0;
+        for &b in pattern {
+            match next() % 8 {
+                0 => {}
+                1 => {
+                    query[len] = b"abc"[next() % 3];
+                    query[len + 1] = b;
+                    len += 2;
+                }
+                2 => {
+                    query[len] = b"abc"[next() % 3];
+                    len += 1;
+                }
+                _ => {
+                    query[len] = b;
+                    len += 1;
+                }
+            }
+            if len >= query.len() - 1 {
+                break;
+            }
+        }
+        let query = core::str::from_utf8(&query[..len]).unwrap();
+        if PATTERNS.contains(&query) {
+            continue;
+        }
+        let mut expected = [("", 0); 6];
+        let mut expected_len = 0;
+        for pattern in PATTERNS {
+            let d = edit_distance(pattern.as_bytes(), query.as_bytes());
+            if d <= 3 {
+                expected[expected_len] = (pattern, d);
+                expected_len += 1;
+            }
+        }
+        let (mut result, len) = f(query);
+        result[..len].sort_unstable();
+        expected[..expected_len].sort_unstable();
+        assert_eq!(&result[..len], &expected[..expected_len], "{query}");
+    }
+}
+
+#[test]
+fn test_suggest_bytes() {
+    let f = |text: &[u8]| {
+        trie_match! {
+            match text {
+                b"abc" => None,
+                [b'a', b'x'] => None,
+                #[suggest(candidates)]
+                w => candidates.map(|(c, d)| (c, d, w.len())).next(),
+            }
+        }
+    };
+    assert_eq!(f(b"ab"), Some((&b"abc"[..], 1, 2)));
+    assert_eq!(f(b"zzzz"), None);
+}
+
+#[test]
+fn test_set_contains() {
+    let set = trie_set!["", "a", "abc", "abd", "b"];
+    assert!(set.contains(""));
+    assert!(set.contains("a"));
+    assert!(set.contains("abc"));
+    assert!(set.contains("abd"));
+    assert!(set.contains("b"));
+    assert!(!set.contains("ab"));
+    assert!(!set.contains("abcd"));
+    assert!(!set.contains("c"));
+    assert!(!set.contains("\u{0}"));
+}
+
+#[test]
+fn test_set_predictive_search() {
+    let set = trie_set!["abd", "b", "", "abc", "a", "bcd", "abcd"];
+    assert!(set
+        .predictive_search("")
+        .eq(["", "a", "abc", "abcd", "abd", "b", "bcd"]));
+    assert!(set.predictive_search("a").eq(["a", "abc", "abcd", "abd"]));
+    assert!(set.predictive_search("ab").eq(["abc", "abcd", "abd"]));
+    assert!(set.predictive_search("abc").eq(["abc", "abcd"]));
+    assert!(set.predictive_search("bc").eq(["bcd"]));
+    assert!(set.predictive_search("abcde").eq([""; 0]));
+    assert!(set.predictive_search("c").eq([""; 0]));
+}
+
+#[test]
+fn test_set_bytes() {
+    let set = trie_set![b"ab", b"a\x00", b"b"];
+    assert!(set.contains(b"a\x00"));
+    assert!(set.predictive_search(b"a").eq([&b"a\x00"[..], &b"ab"[..]]));
+}
+
+#[test]
+fn test_set_empty() {
+    let set = trie_set![];
+    assert!(!set.contains(""));
+    assert!(set.predictive_search("").eq([""; 0]));
+}
+
+#[trie_match_all]
+fn match_all_fn(x: &str, y: &[u8]) -> u32 {
+    let a = match x {
+        "a" => 1,
+        "abc" => 2,
+        w => match w.as_bytes() {
+            b"ab" => 3,
+            [b'b', b'c'] => 4,
+            _ => 5,
+        },
+    };
+    let b = match y.len() {
+        0 => 0,
+        _ => 10,
+    };
+    a + b
+}
+
+struct MatchAll;
+
+#[trie_match_all(min_arms = 3)]
+impl MatchAll {
+    fn f(x: &str) -> &'static str {
+        match x {
+            // This attribute is only available when the expression is rewritten.
+            #[literal(pat)]
+            "a" | "b" => pat,
+            "c" => "c",
+            _ => "",
+        }
+    }
+
+    fn g(x: &str) -> u32 {
+        match x {
+            "a" => 0,
+            _ => 1,
+        }
+    }
+}
+
+#[test]
+fn test_match_all() {
+    assert_eq!(match_all_fn("a", b""), 1);
+    assert_eq!(match_all_fn("abc", b"x"), 12);
+    assert_eq!(match_all_fn("ab", b""), 3);
+    assert_eq!(match_all_fn("bc", b""), 4);
+    assert_eq!(match_all_fn("b", b""), 5);
+    assert_eq!(MatchAll::f("b"), "b");
+    assert_eq!(MatchAll::f("c"), "c");
+    assert_eq!(MatchAll::f("d"), "");
+    assert_eq!(MatchAll::g("a"), 0);
+    assert_eq!(MatchAll::g("b"), 1);
+}
+
+// Match expressions in const contexts are expanded in the const-compatible mode.
+#[trie_match_all]
+const fn match_all_const_fn(x: &[u8]) -> u32 {
+    match x {
+        b"fn" => 1,
+        b"if" => 2,
+        _ => 0,
+    }
+}
+
+#[trie_match_all]
+impl MatchAll {
+    const fn h(x: &str) -> u32 {
+        match x {
+            "fn" => 1,
+            "if" => 2,
+            _ => 0,
+        }
+    }
+}
+
+const MATCH_ALL_QUERY: &str = "while";
+
+#[trie_match_all]
+const MATCH_ALL_CONST: u32 = match MATCH_ALL_QUERY {
+    "if" => 1,
+    "while" => 2,
+    _ => 0,
+};
+
+#[trie_match_all]
+static MATCH_ALL_STATIC: u32 = match MATCH_ALL_QUERY.as_bytes() {
+    b"if" => 1,
+    b"while" => 2,
+    _ => 0,
+};
+
+#[test]
+fn test_match_all_const() {
+    const FN: u32 = match_all_const_fn(b"fn");
+    const IF: u32 = MatchAll::h("if");
+
+    assert_eq!(FN, 1);
+    assert_eq!(match_all_const_fn(b"if"), 2);
+    assert_eq!(match_all_const_fn(b"for"), 0);
+    assert_eq!(IF, 2);
+    assert_eq!(MatchAll::h("else"), 0);
+    assert_eq!(MATCH_ALL_CONST, 2);
+    assert_eq!(MATCH_ALL_STATIC, 2);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_report_strategy() {
+    const fn f(x: &str) -> u32 {
+        trie_match! {
+            #[const_compatible]
+            #[report_strategy]
+            match x {
+                "fn" => 1,
+                "if" => 2,
+                _ => 0,
+            }
+        }
+    }
+    let g = |x: &str| {
+        trie_match! {
+            #[report_strategy]
+            #[strategy(double_array)]
+            match x {
+                "in" => 0,
+                "int" => 1,
+                _ => 2,
+            }
+        }
+    };
+
+    assert_eq!(f("if"), 2);
+    assert_eq!(f("else"), 0);
+    assert_eq!(g("int"), 1);
+    assert_eq!(g("i"), 2);
+}
+
+const fn keyword_id(x: &str) -> u32 {
+    trie_match! {
+        #[const_compatible]
+        #[strategy(double_array)]
+        match x {
+            "fn" => 1,
+            "for" => 2,
+            "if" | "impl" => 3,
+            _ => 0,
+        }
+    }
+}
+
+const fn byte_len(x: &[u8]) -> usize {
+    trie_match! {
+        #[const_compatible]
+        match x {
+            b"abc" | [0, 255] => 1,
+            pat @ b"xyzw" => pat.len(),
+            &[] => 5,
+            _ => 0,
+        }
+    }
+}
+
+const fn literal_len(x: &str) -> usize {
+    trie_match! {
+        #[const_compatible]
+        match x {
+            #[literal(pat)]
+            "a" | "bcd" => pat.len(),
+            _ => 0,
+        }
+    }
+}
+
+const fn remapped_len(x: &[u8]) -> usize {
+    trie_match! {
+        #[const_compatible]
+        #[alphabet_remapping]
+        match x {
+            pat @ (b"\xff\xfe" | b"\xff\xfd\xfd") => pat.len(),
+            _ => 0,
+        }
+    }
+}
+
+const _: () = assert!(keyword_id("fn") == 1);
+const _: () = assert!(keyword_id("for") == 2);
+const _: () = assert!(keyword_id("if") == 3);
+const _: () = assert!(keyword_id("impl") == 3);
+const _: () = assert!(keyword_id("f") == 0);
+const _: () = assert!(keyword_id("form") == 0);
+const _: () = assert!(keyword_id("") == 0);
+const _: () = assert!(byte_len(b"abc") == 1);
+const _: () = assert!(byte_len(&[0, 255]) == 1);
+const _: () = assert!(byte_len(b"xyzw") == 4);
+const _: () = assert!(byte_len(b"") == 5);
+const _: () = assert!(byte_len(b"\0") == 0);
+const _: () = assert!(literal_len("bcd") == 3);
+const _: () = assert!(remapped_len(b"\xff\xfe") == 2);
+const _: () = assert!(remapped_len(b"\xff\xfd\xfd") == 3);
+const _: () = assert!(remapped_len(b"\xff") == 0);
+const _: () = assert!(remapped_len(b"a") == 0);
+const _: () = assert!(literal_len("bcde") == 0);
+const _: () = assert!(literal_len("bc") == 0);
+
+#[test]
+fn test_const_compatible() {
+    const KEYWORDS: [u32; 3] = [keyword_id("for"), keyword_id("impl"), keyword_id("while")];
+
+    assert_eq!(KEYWORDS, [2, 3, 0]);
+    assert_eq!(keyword_id("fn"), 1);
+    assert_eq!(byte_len(b"xyzw"), 4);
+}
+
+trie_match_fn! {
+    #[inline(never)]
+    pub fn classify(x: &str) -> u8 {
+        match x {
+            "a" => 0,
+            "abc" | "bc" => 1,
+            pat @ "bcd" => pat.len() as u8,
+            _ => 9,
+        }
+    }
+}
+
+trie_match_fn! {
+    fn classify_bytes(x: &[u8]) -> u8 {
+        match x {
+            b"ab" => 0,
+            [0, 255] => 1,
+            _ => 2,
+        }
+    }
+}
+
+trie_match_fn! {
+    const fn classify_const(x: &str) -> u8 {
+        #[strategy(double_array)]
+        match x {
+            "fn" => 1,
+            "for" => 2,
+            _ => 0,
+        }
+    }
+}
+
+const _: () = assert!(classify_const("for") == 2);
+const _: () = assert!(classify_const("fo") == 0);
+
+#[test]
+fn test_trie_match_fn() {
+    assert_eq!(classify("a"), 0);
+    assert_eq!(classify("abc"), 1);
+    assert_eq!(classify("bc"), 1);
+    assert_eq!(classify("bcd"), 3);
+    assert_eq!(classify("ab"), 9);
+    assert_eq!(classify::PATTERNS, ["a", "abc", "bc", "bcd"]);
+    assert_eq!(classify::STRATEGY, "length");
+
+    assert_eq!(classify_bytes(b"ab"), 0);
+    assert_eq!(classify_bytes(&[0, 255]), 1);
+    assert_eq!(classify_bytes(b"a"), 2);
+    assert_eq!(classify_bytes::PATTERNS, [&b"ab"[..], &[0, 255]]);
+
+    assert_eq!(classify_const("fn"), 1);
+    assert_eq!(classify_const::PATTERNS, ["fn", "for"]);
+    assert_eq!(classify_const::STRATEGY, "double_array");
+
+    // Only the double-array has slots.
+    assert_eq!(classify::NUM_SLOTS, 0);
+    assert_eq!(classify::FILL_RATIO, 0.0);
+    const _: () = assert!(classify_const::NUM_STATES > 0);
+    const _: () = assert!(classify_const::NUM_STATES <= classify_const::NUM_SLOTS);
+    assert!(
+        (classify_const::FILL_RATIO
+            - classify_const::NUM_STATES as f64 / classify_const::NUM_SLOTS as f64)
+            .abs()
+            < 1e-9
+    );
+}
+
 #[cfg(feature = "cfg_attribute")]
 #[test]
 fn test_cfg_attribute() {
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use trie_match_core::Sparse;
+
+use crate::Strategy;
+
+/// Cost of a conditional branch or a jump table dispatch.
+const COST_BRANCH: f64 = 1.0;
+
+/// Cost of a load from a table that depends on the previous load.
+const COST_LOAD: f64 = 1.5;
+
+/// Cost of a multiplication on the critical path of a hash function.
+const COST_MULTIPLY: f64 = 1.0;
+
+/// Cost of hashing a word of the query in a loop, including the branch at its end.
+const COST_HASH_WORD: f64 = 5.0;
+
+/// Cost of comparing a byte as part of a slice comparison.
+const COST_BYTE: f64 = 0.125;
+
+/// Cost added if the code or the table does not fit in the L1 cache at all. The cost grows
+/// towards this value as the size exceeds [`L1_SIZE`].
+const COST_MISS: f64 = 30.0;
+
+/// Size of the L1 cache assumed by the cost model.
+const L1_SIZE: f64 = 32.0 * 1024.0;
+
+/// Estimated size of the code generated for a pattern by the strategies without tables, in
+/// addition to the pattern bytes.
+const CODE_SIZE_PER_PATTERN: f64 = 64.0;
+
+/// Estimated size of a slot of the double-array.
+const SLOT_SIZE: f64 = 4.0;
+
+/// Size of an entry of the perfect hash table, in addition to the pattern bytes.
+const ENTRY_SIZE: f64 = 24.0;
+
+/// Statistics of a trie used by the cost model.
+struct TrieStats {
+    /// Number of patterns.
+    num_patterns: f64,
+
+    /// Average length of patterns.
+    avg_len: f64,
+
+    /// Average number of children of states with two or more children.
+    branching_factor: f64,
+
+    /// Average size of groups of patterns with the same length, weighted by the number of
+    /// patterns.
+    avg_length_group: f64,
+
+    /// Average number of transitions in the double-array before a pattern reaches its tail.
+    avg_transitions: f64,
+
+    /// Average length of tails of patterns.
+    avg_tail_len: f64,
+
+    /// Ratio of patterns reaching their tails.
+    tail_ratio: f64,
+
+    /// Number of states that are not stored in tails.
+    num_array_states: f64,
+}
+
+impl TrieStats {
+    #[allow(clippy::cast_precision_loss)]
+    fn new(trie: &Sparse<usize>, patterns: &[(Vec<u8>, usize)]) -> Self {
+        let states = trie.preorder();
+        // Parents and the number of children of states in pre-order.
+        let mut parents = vec![usize::MAX; states.len()];
+        let mut num_children = vec![0_usize; states.len()];
+        let mut path: Vec<usize> = vec![];
+        for (i, &(_, depth, _, _)) in states.iter().enumerate() {
+            path.truncate(depth);
+            if let Some(&parent) = path.last() {
+                parents[i] = parent;
+                num_children[parent] += 1;
+            }
+            path.push(i);
+        }
+        let (branching_states, branching_edges) = num_children
+            .iter()
+            .filter(|&&n| n >= 2)
+            .fold((0_usize, 0), |(states, edges), &n| (states + 1, edges + n));
+
+        // A pattern reaches its tail below the deepest ancestor that branches or has a value.
+        let mut num_transitions = 0;
+        let mut num_tails = 0_usize;
+        let mut tail_len_sum = 0;
+        for (i, &(_, depth, _, value)) in states.iter().enumerate() {
+            if value.is_none() {
+                continue;
+            }
+            let mut ancestor = parents[i];
+            while ancestor != usize::MAX
+                && num_children[ancestor] < 2
+                && states[ancestor].3.is_none()
+                && parents[ancestor] != usize::MAX
+            {
+                ancestor = parents[ancestor];
+            }
+            let branch_depth = if ancestor == usize::MAX {
+                0
+            } else {
+                states[ancestor].1 + 1
+            };
+            let tail_len = depth - branch_depth.min(depth);
+            if num_children[i] == 0 && tail_len >= Sparse::<usize>::MIN_TAIL_LENGTH {
+                num_transitions += branch_depth;
+                num_tails += 1;
+                tail_len_sum += tail_len;
+            } else {
+                num_transitions += depth;
+            }
+        }
+
+        let mut length_groups = BTreeMap::new();
+        for (pattern, _) in patterns {
+            *length_groups.entry(pattern.len()).or_insert(0_usize) += 1;
+        }
+        let num_patterns = patterns.len().max(1) as f64;
+        Self {
+            num_patterns,
+            avg_len: patterns.iter().map(|p| p.0.len()).sum::<usize>() as f64 / num_patterns,
+            branching_factor: if branching_states == 0 {
+                1.0
+            } else {
+                branching_edges as f64 / branching_states as f64
+            },
+            avg_length_group: (length_groups.values().map(|&n| n * n).sum::<usize>() as f64
+                / num_patterns)
+                .max(1.0),
+            avg_transitions: num_transitions as f64 / num_patterns,
+            avg_tail_len: tail_len_sum as f64 / num_patterns,
+            tail_ratio: num_tails as f64 / num_patterns,
+            num_array_states: (states.len() - tail_len_sum) as f64,
+        }
+    }
+}
+
+/// Returns the cost added by cache misses for the code or the table of the given size.
+fn miss_cost(size: f64) -> f64 {
+    if size <= L1_SIZE {
+        0.0
+    } else {
+        COST_MISS * (1.0 - L1_SIZE / size)
+    }
+}
+
+/// Returns the estimated cost of each strategy for a query matching a pattern.
+///
+/// The costs are rough estimates based on the number of branches, table loads, and compared
+/// bytes, and on whether the code or the table fits in the L1 cache. The strategies without
+/// tables are cheaper for small pattern sets, but their code grows with the number of patterns.
+#[allow(clippy::suboptimal_flops)]
+fn estimate_costs(stats: &TrieStats) -> [(Strategy, f64); 5] {
+    let compare = COST_BYTE * stats.avg_len;
+    let code_miss = miss_cost(stats.num_patterns * (stats.avg_len + CODE_SIZE_PER_PATTERN));
+
+    // The compiler expands a normal `match` into a binary search over the patterns.
+    let plain_match = COST_BRANCH * (stats.num_patterns.log2() + 1.0) + compare + code_miss;
+    let length = COST_BRANCH * (stats.avg_length_group.log2() + 2.0) + compare + code_miss;
+    // Each node of the decision tree splits the patterns by the branching factor.
+    let depth = if stats.branching_factor > 1.0 {
+        stats.num_patterns.log(stats.branching_factor)
+    } else {
+        stats.num_patterns
+    };
+    let decision_tree = COST_BRANCH * (depth + 1.0) + compare + code_miss;
+    // Loading the first slot is also a dependent load, and a tail needs two more loads of its
+    // entry and its bytes.
+    let double_array = COST_LOAD * (stats.avg_transitions + 2.0 + 2.0 * stats.tail_ratio)
+        + COST_BYTE * stats.avg_tail_len
+        + miss_cost(stats.num_array_states * SLOT_SIZE) / 2.0;
+    // The query is hashed a word at a time, and then the only candidate is compared. The last
+    // word is hashed outside the loop, and only a few cache lines are touched regardless of the
+    // size of the table.
+    let num_loop_words = ((stats.avg_len - 1.0) / 8.0).floor().max(0.0);
+    let perfect_hash = COST_MULTIPLY * 2.0
+        + COST_HASH_WORD * num_loop_words
+        + COST_LOAD * 2.0
+        + compare
+        + miss_cost(stats.num_patterns * (stats.avg_len + ENTRY_SIZE)) / 4.0;
+    [
+        (Strategy::DecisionTree, decision_tree),
+        (Strategy::Length, length),
+        (Strategy::Match, plain_match),
+        (Strategy::DoubleArray, double_array),
+        (Strategy::PerfectHash, perfect_hash),
+    ]
+}
+
+/// Selects the strategy with the lowest estimated cost.
+///
+/// # Arguments
+///
+/// * `trie` - Trie whose values are indices of `patterns`.
+/// * `patterns` - Tuples of the pattern and the arm index.
+pub fn select_strategy(trie: &Sparse<usize>, patterns: &[(Vec<u8>, usize)]) -> Strategy {
+    let stats = TrieStats::new(trie, patterns);
+    estimate_costs(&stats)
+        .into_iter()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(Strategy::DoubleArray, |(strategy, _)| strategy)
+}
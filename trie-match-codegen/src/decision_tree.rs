@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use syn::LitByteStr;
+
+use crate::generate_output;
+
+/// Generates an expression that evaluates to a tuple of the arm, the query, and the matched
+/// literal in the same way as the double-array walkers.
+///
+/// The expression is a tree of nested `match`es on bytes of the query, so no table is loaded and
+/// the compiler can lower each `match` into a jump table. The expression is available in const
+/// contexts.
+///
+/// # Arguments
+///
+/// * `patterns` - Tuples of the pattern and the arm index.
+/// * `wildcard_idx` - Arm index of the wildcard.
+/// * `with_literals` - Whether the matched literal is returned.
+/// * `query_ref` - Expression converting `query` into `&[u8]`.
+pub fn generate_decision_tree(
+    patterns: &[(Vec<u8>, usize)],
+    wildcard_idx: usize,
+    with_literals: bool,
+    query_ref: &TokenStream,
+) -> TokenStream {
+    let candidates: Vec<_> = patterns.iter().collect();
+    let wildcard = generate_output(&[], wildcard_idx, with_literals);
+    let tree = generate_node(&candidates, &wildcard, with_literals);
+    quote! {
+        {
+            let query_ref: &[u8] = #query_ref;
+            let (value, literal) = #tree;
+            (value, query, literal)
+        }
+    }
+}
+
+/// Groups candidates by the byte at the given position. Candidates not longer than the position
+/// are grouped into `None`.
+fn partition<'a>(
+    candidates: &[&'a (Vec<u8>, usize)],
+    pos: usize,
+) -> BTreeMap<Option<u8>, Vec<&'a (Vec<u8>, usize)>> {
+    let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for &candidate in candidates {
+        groups
+            .entry(candidate.0.get(pos).copied())
+            .or_default()
+            .push(candidate);
+    }
+    groups
+}
+
+/// Generates a node of the decision tree that distinguishes the candidates.
+///
+/// The node discriminates the candidates by the byte at the position that minimizes the size of
+/// the largest group, so the depth of the tree is not limited to the order of bytes. A single
+/// candidate is compared with the whole query because the bytes at other positions are not yet
+/// checked.
+fn generate_node(
+    candidates: &[&(Vec<u8>, usize)],
+    wildcard: &TokenStream,
+    with_literals: bool,
+) -> TokenStream {
+    if candidates.is_empty() {
+        return wildcard.clone();
+    }
+    if let [(bytes, arm)] = candidates {
+        let lit = LitByteStr::new(bytes, Span::call_site());
+        let out = generate_output(bytes, *arm, with_literals);
+        return quote! {
+            match query_ref {
+                #lit => #out,
+                _ => #wildcard,
+            }
+        };
+    }
+    // Patterns are distinct, so at least one position splits two or more candidates.
+    let max_len = candidates.iter().map(|c| c.0.len()).max().unwrap_or(0);
+    let (pos, groups) = (0..max_len)
+        .map(|pos| (pos, partition(candidates, pos)))
+        .filter(|(_, groups)| groups.len() >= 2)
+        .min_by_key(|(_, groups)| groups.values().map(Vec::len).max())
+        .unwrap();
+    let mut short = quote! { #wildcard };
+    let mut arms = vec![];
+    for (b, group) in groups {
+        let node = generate_node(&group, wildcard, with_literals);
+        if let Some(b) = b {
+            let b = Literal::u8_unsuffixed(b);
+            arms.push(quote! { #b => #node });
+        } else {
+            short = node;
+        }
+    }
+    let pos = Literal::usize_unsuffixed(pos);
+    quote! {
+        if query_ref.len() > #pos {
+            match query_ref[#pos] {
+                #( #arms, )*
+                _ => #wildcard,
+            }
+        } else {
+            #short
+        }
+    }
+}
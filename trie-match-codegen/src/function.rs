@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use proc_macro2::{Ident, Span};
+use quote::quote;
+use syn::{parse_quote, Arm, Error, Expr, ItemFn, LitByteStr, LitStr, Pat};
+
+use crate::{trie_match_inner, Strategy};
+
+static ERROR_DUPLICATE_PATTERN: &str = "duplicate pattern";
+
+enum Pattern {
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+impl Pattern {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Str(s) => s.as_bytes(),
+            Self::Bytes(b) => b,
+        }
+    }
+
+    fn to_pat(&self) -> Pat {
+        match self {
+            Self::Str(s) => {
+                let lit = LitStr::new(s, Span::call_site());
+                parse_quote! { #lit }
+            }
+            Self::Bytes(b) => {
+                let lit = LitByteStr::new(b, Span::call_site());
+                parse_quote! { #lit }
+            }
+        }
+    }
+}
+
+/// Generator of a function whose body is a match expression expanded by `trie_match!`.
+///
+/// Arms with the same body are merged into a single arm.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match_codegen::MatchFunction;
+///
+/// let mut function = MatchFunction::new("fn color(query: &[u8]) -> Option<u32>", "query");
+/// function
+///     .arm("red", "Some(0xff0000)")
+///     .arm("green", "Some(0x00ff00)")
+///     .arm_bytes(b"\xffblue", "Some(0x0000ff)")
+///     .wildcard("None");
+///
+/// let code = function.generate().unwrap();
+/// assert!(code.starts_with("fn color"));
+/// ```
+pub struct MatchFunction {
+    signature: String,
+    query: String,
+
+    /// Patterns and the body of each arm.
+    arms: Vec<(Vec<Pattern>, String)>,
+
+    /// Indices of `arms` for each body.
+    body_map: HashMap<String, usize>,
+
+    wildcard: Option<String>,
+    const_compatible: bool,
+    alphabet_remapping: bool,
+    minimize: bool,
+    strategy: Strategy,
+}
+
+impl MatchFunction {
+    /// Creates a new generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `signature` - Signature of the function, such as `pub fn f(query: &str) -> u32`.
+    ///   Attributes and doc comments can precede it.
+    /// * `query` - Expression to be matched, such as `query`.
+    pub fn new(signature: impl Into<String>, query: impl Into<String>) -> Self {
+        Self {
+            signature: signature.into(),
+            query: query.into(),
+            arms: vec![],
+            body_map: HashMap::new(),
+            wildcard: None,
+            const_compatible: false,
+            alphabet_remapping: false,
+            minimize: false,
+            strategy: Strategy::default(),
+        }
+    }
+
+    fn add_arm(&mut self, pattern: Pattern, body: String) -> &mut Self {
+        if let Some(&i) = self.body_map.get(&body) {
+            self.arms[i].0.push(pattern);
+        } else {
+            self.body_map.insert(body.clone(), self.arms.len());
+            self.arms.push((vec![pattern], body));
+        }
+        self
+    }
+
+    /// Adds an arm matching a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - String pattern.
+    /// * `body` - Expression evaluated if the query matches the pattern.
+    pub fn arm(&mut self, pattern: impl Into<String>, body: impl Into<String>) -> &mut Self {
+        self.add_arm(Pattern::Str(pattern.into()), body.into())
+    }
+
+    /// Adds an arm matching a byte string.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Byte string pattern.
+    /// * `body` - Expression evaluated if the query matches the pattern.
+    pub fn arm_bytes(&mut self, pattern: impl Into<Vec<u8>>, body: impl Into<String>) -> &mut Self {
+        self.add_arm(Pattern::Bytes(pattern.into()), body.into())
+    }
+
+    /// Sets the expression evaluated if the query matches no pattern.
+    pub fn wildcard(&mut self, body: impl Into<String>) -> &mut Self {
+        self.wildcard = Some(body.into());
+        self
+    }
+
+    /// Generates code available in const contexts, which is equivalent to the
+    /// `#[const_compatible]` attribute of `trie_match!`.
+    pub fn const_compatible(&mut self) -> &mut Self {
+        self.const_compatible = true;
+        self
+    }
+
+    /// Remaps bytes to dense codes, which is equivalent to the `#[alphabet_remapping]` attribute
+    /// of `trie_match!`.
+    pub fn alphabet_remapping(&mut self) -> &mut Self {
+        self.alphabet_remapping = true;
+        self
+    }
+
+    /// Merges equivalent subtrees of the trie, which is equivalent to the `#[minimize]` attribute
+    /// of `trie_match!`.
+    pub fn minimize(&mut self) -> &mut Self {
+        self.minimize = true;
+        self
+    }
+
+    /// Sets the code generation backend, which is equivalent to the `#[strategy(...)]`
+    /// attribute of `trie_match!`.
+    pub fn strategy(&mut self, strategy: Strategy) -> &mut Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Generates the source code of the function.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature or an expression cannot be parsed, if a pattern is
+    /// added twice, or if the wildcard is not set.
+    pub fn generate(&self) -> Result<String, Error> {
+        let ItemFn {
+            attrs, vis, sig, ..
+        } = syn::parse_str(&format!("{} {{}}", self.signature))?;
+        let query: Expr = syn::parse_str(&self.query)?;
+
+        let mut pattern_set = HashSet::new();
+        let mut arms: Vec<Arm> = vec![];
+        for (patterns, body) in &self.arms {
+            for pattern in patterns {
+                if !pattern_set.insert(pattern.as_bytes()) {
+                    let pat = pattern.to_pat();
+                    return Err(Error::new(
+                        Span::call_site(),
+                        format!("{ERROR_DUPLICATE_PATTERN}: {}", quote! { #pat }),
+                    ));
+                }
+            }
+            let pats = patterns.iter().map(Pattern::to_pat);
+            let body: Expr = syn::parse_str(body)?;
+            arms.push(parse_quote! { #( #pats )|* => #body });
+        }
+        if let Some(wildcard) = &self.wildcard {
+            let wildcard: Expr = syn::parse_str(wildcard)?;
+            arms.push(parse_quote! { _ => #wildcard });
+        }
+
+        let const_compatible = self
+            .const_compatible
+            .then(|| quote! { #[const_compatible] });
+        let alphabet_remapping = self
+            .alphabet_remapping
+            .then(|| quote! { #[alphabet_remapping] });
+        let minimize = self.minimize.then(|| quote! { #[minimize] });
+        let strategy = (self.strategy != Strategy::default()).then(|| {
+            let name = Ident::new(self.strategy.name(), Span::call_site());
+            quote! { #[strategy(#name)] }
+        });
+        let expr = trie_match_inner(parse_quote! {
+            #const_compatible
+            #alphabet_remapping
+            #minimize
+            #strategy
+            match #query {
+                #( #arms, )*
+            }
+        })?;
+        Ok(quote! {
+            #( #attrs )*
+            #vis #sig {
+                #expr
+            }
+        }
+        .to_string())
+    }
+
+    /// Generates the source code of the function and writes it to the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if [`generate`](Self::generate) fails or if the file cannot be written.
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let code = self
+            .generate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        fs::write(path, code)
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use syn::LitByteStr;
+
+use crate::generate_output;
+
+/// Generates an expression that evaluates to a tuple of the arm, the query, and the matched
+/// literal in the same way as the double-array walkers.
+///
+/// The expression dispatches on the length of the query, and then matches the query with the
+/// patterns of the same length, so a query is compared with only a few patterns if the lengths
+/// are mostly distinct. The expression is available in const contexts.
+///
+/// # Arguments
+///
+/// * `patterns` - Tuples of the pattern and the arm index.
+/// * `wildcard_idx` - Arm index of the wildcard.
+/// * `with_literals` - Whether the matched literal is returned.
+/// * `query_ref` - Expression converting `query` into `&[u8]`.
+pub fn generate_length_dispatch(
+    patterns: &[(Vec<u8>, usize)],
+    wildcard_idx: usize,
+    with_literals: bool,
+    query_ref: &TokenStream,
+) -> TokenStream {
+    let mut groups: BTreeMap<usize, Vec<&(Vec<u8>, usize)>> = BTreeMap::new();
+    for pattern in patterns {
+        groups.entry(pattern.0.len()).or_default().push(pattern);
+    }
+    let wildcard = generate_output(&[], wildcard_idx, with_literals);
+    let groups = groups.into_iter().map(|(len, patterns)| {
+        let len = Literal::usize_unsuffixed(len);
+        let arms = patterns.into_iter().map(|(bytes, arm)| {
+            let lit = LitByteStr::new(bytes, Span::call_site());
+            let out = generate_output(bytes, *arm, with_literals);
+            quote! { #lit => #out }
+        });
+        quote! {
+            #len => match query_ref {
+                #( #arms, )*
+                _ => #wildcard,
+            }
+        }
+    });
+    quote! {
+        {
+            let query_ref: &[u8] = #query_ref;
+            let (value, literal) = match query_ref.len() {
+                #( #groups, )*
+                _ => #wildcard,
+            };
+            (value, query, literal)
+        }
+    }
+}
@@ -0,0 +1,1529 @@
+//! # trie-match-codegen
+//!
+//! The code generator of the [`trie-match`](https://docs.rs/trie-match) macros.
+//!
+//! Since procedural macros are expanded on every build, a large match expression slows down
+//! incremental builds. This crate generates the same code in `build.rs` instead, so that it is
+//! generated only when the data changes.
+//!
+//! ```no_run
+//! // build.rs
+//! use std::env;
+//! use std::path::Path;
+//!
+//! use trie_match_codegen::MatchFunction;
+//!
+//! let mut function = MatchFunction::new("pub fn keyword_id(query: &str) -> u32", "query");
+//! function.arm("if", "1").arm("else", "2").arm("match", "3").wildcard("0");
+//!
+//! let path = Path::new(&env::var("OUT_DIR").unwrap()).join("keyword.rs");
+//! function.write_to_file(path).unwrap();
+//! ```
+//!
+//! The generated file can be included as follows:
+//!
+//! ```ignore
+//! include!(concat!(env!("OUT_DIR"), "/keyword.rs"));
+//! ```
+
+mod cost;
+mod decision_tree;
+mod function;
+mod length;
+mod padding;
+mod perfect_hash;
+mod plain;
+mod strategy;
+mod suggest;
+
+use std::{collections::BTreeMap, iter};
+
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    spanned::Spanned, Arm, Attribute, Error, Expr, ExprLit, ExprMatch, Lit, LitByteStr, Pat,
+    PatIdent, PatOr, PatReference, PatSlice, PatWild,
+};
+use trie_match_core::{BuildOptions, DoubleArray, Segment, Sparse, TAIL_BASE};
+
+use crate::cost::select_strategy;
+use crate::decision_tree::generate_decision_tree;
+use crate::length::generate_length_dispatch;
+use crate::padding::{sentinel_check, Padded};
+use crate::perfect_hash::generate_perfect_hash;
+use crate::plain::generate_plain_match;
+use crate::suggest::{generate_suggestions, SuggestBinding};
+
+pub use crate::function::MatchFunction;
+pub use crate::strategy::Strategy;
+pub use trie_match_core::{LayoutStats, Placement};
+
+static ERROR_UNEXPECTED_PATTERN: &str =
+    "`trie_match` only supports string literals, byte string literals, and u8 slices as patterns";
+static ERROR_ATTRIBUTE_NOT_SUPPORTED: &str = "attribute not supported here";
+static ERROR_GUARD_NOT_SUPPORTED: &str = "match guard not supported";
+static ERROR_UNREACHABLE_PATTERN: &str = "unreachable pattern";
+static ERROR_PATTERN_NOT_COVERED: &str = "non-exhaustive patterns: `_` not covered";
+static ERROR_EXPECTED_U8_LITERAL: &str = "expected `u8` integer literal";
+static ERROR_VARIABLE_NOT_MATCH: &str = "variable is not bound in all patterns";
+static ERROR_DUPLICATE_LITERAL_ATTRIBUTE: &str = "duplicate `literal` attribute";
+static ERROR_LITERAL_WILDCARD: &str = "`literal` attribute is not available for wildcard patterns";
+static ERROR_DUPLICATE_SUGGEST_ATTRIBUTE: &str = "duplicate `suggest` attribute";
+static ERROR_SUGGEST_NOT_WILDCARD: &str =
+    "`suggest` attribute is only available for wildcard patterns";
+
+static ERROR_DUPLICATE_CONST_COMPATIBLE_ATTRIBUTE: &str = "duplicate `const_compatible` attribute";
+static ERROR_DUPLICATE_ALPHABET_REMAPPING_ATTRIBUTE: &str =
+    "duplicate `alphabet_remapping` attribute";
+static ERROR_DUPLICATE_MINIMIZE_ATTRIBUTE: &str = "duplicate `minimize` attribute";
+static ERROR_DUPLICATE_STRATEGY_ATTRIBUTE: &str = "duplicate `strategy` attribute";
+static ERROR_DUPLICATE_REPORT_STRATEGY_ATTRIBUTE: &str = "duplicate `report_strategy` attribute";
+static ERROR_PERFECT_HASH_NOT_FOUND: &str =
+    "failed to find a perfect hash function of the patterns";
+static ERROR_SUGGEST_CONST_COMPATIBLE: &str =
+    "`suggest` attribute is not available in the const-compatible mode";
+static ERROR_ATTRIBUTE_NOT_SUPPORTED_CFG: &str =
+    "attribute not supported here\nnote: consider enabling the `cfg_attribute` feature: \
+    https://docs.rs/trie-match/latest/trie_match/#cfg-attribute";
+
+/// Converts a literal pattern into a byte sequence.
+///
+/// # Errors
+///
+/// Returns an error if the literal is neither a string literal nor a byte string literal.
+pub fn convert_literal_pattern(pat: &ExprLit) -> Result<Option<Vec<u8>>, Error> {
+    let ExprLit { attrs, lit } = pat;
+    if let Some(attr) = attrs.first() {
+        return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
+    }
+    match lit {
+        Lit::Str(s) => Ok(Some(s.value().into())),
+        Lit::ByteStr(s) => Ok(Some(s.value())),
+        _ => Err(Error::new(lit.span(), ERROR_UNEXPECTED_PATTERN)),
+    }
+}
+
+/// Converts a slice pattern into a byte sequence.
+fn convert_slice_pattern(pat: &PatSlice) -> Result<Option<Vec<u8>>, Error> {
+    let PatSlice { attrs, elems, .. } = pat;
+    if let Some(attr) = attrs.first() {
+        return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
+    }
+    let mut result = vec![];
+    for elem in elems {
+        match elem {
+            Pat::Lit(ExprLit { attrs, lit }) => {
+                if let Some(attr) = attrs.first() {
+                    return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
+                }
+                match lit {
+                    Lit::Int(i) => {
+                        let int_type = i.suffix();
+                        if int_type != "u8" && !int_type.is_empty() {
+                            return Err(Error::new(i.span(), ERROR_EXPECTED_U8_LITERAL));
+                        }
+                        result.push(i.base10_parse::<u8>()?);
+                    }
+                    Lit::Byte(b) => {
+                        result.push(b.value());
+                    }
+                    _ => {
+                        return Err(Error::new(elem.span(), ERROR_EXPECTED_U8_LITERAL));
+                    }
+                }
+            }
+            _ => {
+                return Err(Error::new(elem.span(), ERROR_EXPECTED_U8_LITERAL));
+            }
+        }
+    }
+    Ok(Some(result))
+}
+
+/// Checks a wildcard pattern and returns `None`.
+///
+/// The reason the type is `Result<Option<Vec<u8>>, Error>` instead of `Result<(), Error>` is for
+/// consistency with other functions.
+fn convert_wildcard_pattern(pat: &PatWild) -> Result<Option<Vec<u8>>, Error> {
+    let PatWild { attrs, .. } = pat;
+    if let Some(attr) = attrs.first() {
+        return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
+    }
+    Ok(None)
+}
+
+/// Converts a reference pattern (e.g. `&[0, 1, ...]`) into a byte sequence.
+fn convert_reference_pattern(pat: &PatReference) -> Result<Option<Vec<u8>>, Error> {
+    let PatReference { attrs, pat, .. } = pat;
+    if let Some(attr) = attrs.first() {
+        return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
+    }
+    match &**pat {
+        Pat::Lit(pat) => convert_literal_pattern(pat),
+        Pat::Slice(pat) => convert_slice_pattern(pat),
+        Pat::Reference(pat) => convert_reference_pattern(pat),
+        _ => Err(Error::new(pat.span(), ERROR_UNEXPECTED_PATTERN)),
+    }
+}
+
+struct PatternBytes {
+    /// Bound variable identifier.
+    ident: Option<PatIdent>,
+
+    /// Byte sequence of this pattern. `None` is for a wildcard.
+    bytes: Option<Vec<u8>>,
+
+    /// Whether this pattern is a string literal.
+    is_str: bool,
+}
+
+impl PatternBytes {
+    const fn new(ident: Option<PatIdent>, bytes: Option<Vec<u8>>) -> Self {
+        Self {
+            ident,
+            bytes,
+            is_str: false,
+        }
+    }
+
+    const fn new_str(ident: Option<PatIdent>, bytes: Option<Vec<u8>>) -> Self {
+        Self {
+            ident,
+            bytes,
+            is_str: true,
+        }
+    }
+}
+
+/// Retrieves pattern strings from the given token.
+///
+/// None indicates a wild card pattern (`_`).
+fn retrieve_match_patterns(
+    pat: &Pat,
+    ident: Option<PatIdent>,
+    pat_bytes_set: &mut Vec<PatternBytes>,
+    pat_set: &mut Vec<Pat>,
+) -> Result<(), Error> {
+    match pat {
+        Pat::Lit(lit) => {
+            pat_set.push(pat.clone());
+            let bytes = convert_literal_pattern(lit)?;
+            if matches!(lit.lit, Lit::Str(_)) {
+                pat_bytes_set.push(PatternBytes::new_str(ident, bytes));
+            } else {
+                pat_bytes_set.push(PatternBytes::new(ident, bytes));
+            }
+        }
+        Pat::Slice(slice) => {
+            pat_set.push(pat.clone());
+            pat_bytes_set.push(PatternBytes::new(ident, convert_slice_pattern(slice)?));
+        }
+        Pat::Wild(pat) => {
+            pat_bytes_set.push(PatternBytes::new(ident, convert_wildcard_pattern(pat)?));
+        }
+        Pat::Reference(reference) => {
+            pat_set.push(pat.clone());
+            pat_bytes_set.push(PatternBytes::new(
+                ident,
+                convert_reference_pattern(reference)?,
+            ));
+        }
+        Pat::Ident(pat) => {
+            if let Some(attr) = pat.attrs.first() {
+                return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
+            }
+            let mut pat = pat.clone();
+            if let Some((_, subpat)) = pat.subpat.take() {
+                retrieve_match_patterns(&subpat, Some(pat), pat_bytes_set, pat_set)?;
+            } else {
+                pat_bytes_set.push(PatternBytes::new(Some(pat), None));
+            }
+        }
+        Pat::Paren(pat) => {
+            retrieve_match_patterns(&pat.pat, ident, pat_bytes_set, pat_set)?;
+        }
+        Pat::Or(PatOr {
+            attrs,
+            leading_vert: None,
+            cases,
+        }) => {
+            if let Some(attr) = attrs.first() {
+                return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED));
+            }
+            for pat in cases {
+                retrieve_match_patterns(pat, ident.clone(), pat_bytes_set, pat_set)?;
+            }
+        }
+        _ => {
+            return Err(Error::new(pat.span(), ERROR_UNEXPECTED_PATTERN));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the patterns of the arms in order of appearance, excluding wildcards, and whether all
+/// of them are string literals.
+///
+/// # Errors
+///
+/// Returns an error if a pattern is not supported.
+pub fn match_patterns(arms: &[Arm]) -> Result<(Vec<Vec<u8>>, bool), Error> {
+    let mut patterns = vec![];
+    let mut is_str = true;
+    for arm in arms {
+        let mut pat_bytes_set = vec![];
+        retrieve_match_patterns(&arm.pat, None, &mut pat_bytes_set, &mut vec![])?;
+        for PatternBytes {
+            bytes, is_str: s, ..
+        } in pat_bytes_set
+        {
+            if let Some(bytes) = bytes {
+                is_str &= s;
+                patterns.push(bytes);
+            }
+        }
+    }
+    Ok((patterns, is_str))
+}
+
+/// Attributes of an arm handled by this macro.
+#[derive(Default)]
+struct ArmAttributes {
+    /// Identifier specified by `#[literal(...)]`.
+    literal: Option<Ident>,
+
+    /// Arguments of `#[suggest(...)]`.
+    suggest: Option<SuggestBinding>,
+}
+
+/// Extracts the `#[literal(...)]` and `#[suggest(...)]` attributes from the attributes of an arm.
+fn take_arm_attributes(attrs: &mut Vec<Attribute>) -> Result<ArmAttributes, Error> {
+    let mut arm_attrs = ArmAttributes::default();
+    let mut rest = vec![];
+    for attr in attrs.drain(..) {
+        if attr.path().is_ident("literal") {
+            if arm_attrs.literal.is_some() {
+                return Err(Error::new(attr.span(), ERROR_DUPLICATE_LITERAL_ATTRIBUTE));
+            }
+            arm_attrs.literal.replace(attr.parse_args()?);
+        } else if attr.path().is_ident("suggest") {
+            if arm_attrs.suggest.is_some() {
+                return Err(Error::new(attr.span(), ERROR_DUPLICATE_SUGGEST_ATTRIBUTE));
+            }
+            arm_attrs.suggest.replace(attr.parse_args()?);
+        } else {
+            rest.push(attr);
+        }
+    }
+    *attrs = rest;
+    Ok(arm_attrs)
+}
+
+/// Attributes of a match expression handled by this macro.
+#[derive(Default)]
+#[allow(clippy::struct_excessive_bools)]
+struct MatchAttributes {
+    /// Whether `#[const_compatible]` is specified.
+    const_compatible: bool,
+
+    /// Whether `#[alphabet_remapping]` is specified.
+    alphabet_remapping: bool,
+
+    /// Whether `#[minimize]` is specified.
+    minimize: bool,
+
+    /// Backend specified by `#[strategy(...)]`, if any.
+    strategy: Option<Strategy>,
+
+    /// Whether `#[report_strategy]` is specified.
+    report_strategy: bool,
+}
+
+/// Extracts the `#[const_compatible]`, `#[alphabet_remapping]`, `#[minimize]`,
+/// `#[strategy(...)]`, and `#[report_strategy]` attributes from the attributes of a match
+/// expression.
+fn take_match_attributes(attrs: &mut Vec<Attribute>) -> Result<MatchAttributes, Error> {
+    let mut match_attrs = MatchAttributes::default();
+    let mut rest = vec![];
+    for attr in attrs.drain(..) {
+        if attr.path().is_ident("const_compatible") {
+            if match_attrs.const_compatible {
+                return Err(Error::new(
+                    attr.span(),
+                    ERROR_DUPLICATE_CONST_COMPATIBLE_ATTRIBUTE,
+                ));
+            }
+            attr.meta.require_path_only()?;
+            match_attrs.const_compatible = true;
+        } else if attr.path().is_ident("alphabet_remapping") {
+            if match_attrs.alphabet_remapping {
+                return Err(Error::new(
+                    attr.span(),
+                    ERROR_DUPLICATE_ALPHABET_REMAPPING_ATTRIBUTE,
+                ));
+            }
+            attr.meta.require_path_only()?;
+            match_attrs.alphabet_remapping = true;
+        } else if attr.path().is_ident("minimize") {
+            if match_attrs.minimize {
+                return Err(Error::new(attr.span(), ERROR_DUPLICATE_MINIMIZE_ATTRIBUTE));
+            }
+            attr.meta.require_path_only()?;
+            match_attrs.minimize = true;
+        } else if attr.path().is_ident("strategy") {
+            if match_attrs.strategy.is_some() {
+                return Err(Error::new(attr.span(), ERROR_DUPLICATE_STRATEGY_ATTRIBUTE));
+            }
+            match_attrs.strategy = Some(attr.parse_args()?);
+        } else if attr.path().is_ident("report_strategy") {
+            if match_attrs.report_strategy {
+                return Err(Error::new(
+                    attr.span(),
+                    ERROR_DUPLICATE_REPORT_STRATEGY_ATTRIBUTE,
+                ));
+            }
+            attr.meta.require_path_only()?;
+            match_attrs.report_strategy = true;
+        } else {
+            rest.push(attr);
+        }
+    }
+    *attrs = rest;
+    Ok(match_attrs)
+}
+
+/// Identifier bound to the matched pattern literal by the `#[literal(...)]` attribute.
+struct LiteralBinding {
+    ident: Ident,
+
+    /// Whether all patterns of the arm are string literals. If `true`, the literal is bound as
+    /// `&'static str`, otherwise as `&'static [u8]`.
+    is_str: bool,
+}
+
+struct MatchInfo {
+    bodies: Vec<Expr>,
+    pattern_map: BTreeMap<Vec<u8>, usize>,
+    wildcard_idx: usize,
+    bound_vals: Vec<Option<PatIdent>>,
+    literal_binds: Vec<Option<LiteralBinding>>,
+    suggest: Option<SuggestBinding>,
+
+    /// Whether all patterns are string literals.
+    is_str: bool,
+
+    pat_set: Vec<Pat>,
+}
+
+fn parse_match_arms(arms: Vec<Arm>) -> Result<MatchInfo, Error> {
+    let mut pattern_map = BTreeMap::new();
+    let mut wildcard_idx = None;
+    let mut bound_vals = vec![];
+    let mut literal_binds = vec![];
+    let mut suggest = None;
+    let mut all_str = true;
+    let mut bodies = vec![];
+    let mut pat_set = vec![];
+    let mut i = 0;
+    #[allow(clippy::explicit_counter_loop)]
+    for Arm {
+        mut attrs,
+        pat,
+        guard,
+        body,
+        ..
+    } in arms
+    {
+        let ArmAttributes {
+            literal,
+            suggest: arm_suggest,
+        } = take_arm_attributes(&mut attrs)?;
+
+        // `cfg` attributes are evaluated by `trie_match!` before calling this function.
+        if let Some(attr) = attrs.first() {
+            return Err(Error::new(attr.span(), ERROR_ATTRIBUTE_NOT_SUPPORTED_CFG));
+        }
+
+        if let Some((if_token, _)) = guard {
+            return Err(Error::new(if_token.span(), ERROR_GUARD_NOT_SUPPORTED));
+        }
+        let mut pat_bytes_set = vec![];
+        retrieve_match_patterns(&pat, None, &mut pat_bytes_set, &mut pat_set)?;
+        let bound_val = pat_bytes_set[0].ident.clone();
+        let is_str = pat_bytes_set.iter().all(|p| p.is_str || p.bytes.is_none());
+        all_str &= is_str;
+        for PatternBytes { ident, bytes, .. } in pat_bytes_set {
+            if ident != bound_val {
+                return Err(Error::new(
+                    ident.or(bound_val).unwrap().span(),
+                    ERROR_VARIABLE_NOT_MATCH,
+                ));
+            }
+            if let Some(bytes) = bytes {
+                if pattern_map.contains_key(&bytes) {
+                    return Err(Error::new(pat.span(), ERROR_UNREACHABLE_PATTERN));
+                }
+                pattern_map.insert(bytes, i);
+            } else {
+                if let Some(literal) = &literal {
+                    return Err(Error::new(literal.span(), ERROR_LITERAL_WILDCARD));
+                }
+                if wildcard_idx.is_some() {
+                    return Err(Error::new(pat.span(), ERROR_UNREACHABLE_PATTERN));
+                }
+                wildcard_idx.replace(i);
+            }
+        }
+        if let Some(arm_suggest) = arm_suggest {
+            if wildcard_idx != Some(i) {
+                return Err(Error::new(
+                    arm_suggest.ident.span(),
+                    ERROR_SUGGEST_NOT_WILDCARD,
+                ));
+            }
+            suggest = Some(arm_suggest);
+        }
+        bound_vals.push(bound_val);
+        literal_binds.push(literal.map(|ident| LiteralBinding { ident, is_str }));
+        bodies.push(*body);
+        i += 1;
+    }
+    let Some(wildcard_idx) = wildcard_idx else {
+        return Err(Error::new(Span::call_site(), ERROR_PATTERN_NOT_COVERED));
+    };
+    Ok(MatchInfo {
+        bodies,
+        pattern_map,
+        wildcard_idx,
+        bound_vals,
+        literal_binds,
+        suggest,
+        is_str: all_str,
+        pat_set,
+    })
+}
+
+/// Generates an arm of the match expression that receives a tuple of the arm index, the query,
+/// and the matched literal.
+fn generate_arm(
+    i: usize,
+    body: &Expr,
+    bound_val: Option<PatIdent>,
+    literal_bind: Option<&LiteralBinding>,
+    suggest: Option<&SuggestBinding>,
+) -> TokenStream {
+    let i = format_ident!("V{i}");
+    let bound_val = bound_val.map_or_else(|| quote! { _ }, |val| quote! { #val });
+    if let Some(SuggestBinding { ident, .. }) = suggest {
+        return quote! {
+            (__TrieMatchValue::#i, __trie_match_query, _) => {
+                let #ident = __TrieMatchSuggestions::new(
+                    ::core::convert::AsRef::<[u8]>::as_ref(&__trie_match_query),
+                );
+                let #bound_val = __trie_match_query;
+                #body
+            }
+        };
+    }
+    match literal_bind {
+        Some(LiteralBinding {
+            ident,
+            is_str: true,
+        }) => quote! {
+            (__TrieMatchValue::#i, #bound_val, __trie_match_literal) => {
+                // Safety: All patterns of this arm are string literals.
+                let #ident: &'static str = unsafe {
+                    ::core::str::from_utf8_unchecked(__trie_match_literal)
+                };
+                #body
+            }
+        },
+        Some(LiteralBinding {
+            ident,
+            is_str: false,
+        }) => quote! {
+            (__TrieMatchValue::#i, #bound_val, #ident) => #body
+        },
+        None => quote! { (__TrieMatchValue::#i, #bound_val, _) => #body },
+    }
+}
+
+/// Expands a match expression in the same way as `trie_match!`.
+///
+/// # Errors
+///
+/// Returns an error if the match expression is not supported.
+pub fn trie_match_inner(input: ExprMatch) -> Result<TokenStream, Error> {
+    trie_match_with_strategy(input).map(|(expr, _)| expr)
+}
+
+/// Expands a match expression in the same way as `trie_match!`, and returns the expression and
+/// the strategy used for it. The strategy is never [`Strategy::Auto`].
+///
+/// # Errors
+///
+/// Returns an error if the match expression is not supported.
+///
+/// # Examples
+///
+/// ```
+/// use syn::parse_quote;
+/// use trie_match_codegen::{trie_match_with_strategy, Strategy};
+///
+/// let (_, strategy) = trie_match_with_strategy(parse_quote! {
+///     #[strategy(length)]
+///     match query {
+///         "a" => 1,
+///         _ => 0,
+///     }
+/// })
+/// .unwrap();
+/// assert_eq!(strategy, Strategy::Length);
+/// ```
+pub fn trie_match_with_strategy(input: ExprMatch) -> Result<(TokenStream, Strategy), Error> {
+    trie_match_expansion(input).map(|expansion| (expansion.expr, expansion.strategy))
+}
+
+/// Match expression expanded by [`trie_match_expansion`].
+#[derive(Clone, Debug)]
+pub struct Expansion {
+    /// Expanded expression.
+    pub expr: TokenStream,
+
+    /// Strategy used for the expression, which is never [`Strategy::Auto`].
+    pub strategy: Strategy,
+
+    /// Numbers of slots and states of the double-array, or `None` if the strategy does not use
+    /// a double-array. The slots are counted after the densest of [`Placement::ALL`] is taken.
+    pub layout: Option<LayoutStats>,
+}
+
+/// Expands a match expression in the same way as `trie_match!`, and returns the expression with
+/// the strategy and the layout of the tables, which helps track the size of the tables.
+///
+/// # Errors
+///
+/// Returns an error if the match expression is not supported.
+///
+/// # Examples
+///
+/// ```
+/// use syn::parse_quote;
+/// use trie_match_codegen::{trie_match_expansion, Strategy};
+///
+/// let expansion = trie_match_expansion(parse_quote! {
+///     #[strategy(double_array)]
+///     match query {
+///         "abc" | "abd" => 1,
+///         _ => 0,
+///     }
+/// })
+/// .unwrap();
+/// assert_eq!(expansion.strategy, Strategy::DoubleArray);
+///
+/// let layout = expansion.layout.unwrap();
+/// assert!(layout.num_states <= layout.num_slots);
+/// assert!(layout.fill_ratio() > 0.5);
+/// ```
+pub fn trie_match_expansion(input: ExprMatch) -> Result<Expansion, Error> {
+    let ExprMatch {
+        mut attrs,
+        expr,
+        arms,
+        ..
+    } = input;
+    let match_attrs = take_match_attributes(&mut attrs)?;
+    let MatchAttributes {
+        const_compatible,
+        strategy,
+        ..
+    } = match_attrs;
+    let MatchInfo {
+        bodies,
+        pattern_map,
+        wildcard_idx,
+        bound_vals,
+        literal_binds,
+        suggest,
+        is_str,
+        pat_set,
+    } = parse_match_arms(arms)?;
+    if let (true, Some(SuggestBinding { ident, .. })) = (const_compatible, &suggest) {
+        return Err(Error::new(ident.span(), ERROR_SUGGEST_CONST_COMPATIBLE));
+    }
+
+    // The trie stores indices of `patterns`, and `patterns.len()` indicates the wildcard.
+    let mut patterns = vec![];
+    let mut trie = Sparse::new();
+    for (k, v) in pattern_map {
+        if v == wildcard_idx {
+            continue;
+        }
+        trie.add(&k, patterns.len());
+        patterns.push((k, v));
+    }
+    let arm = bodies
+        .iter()
+        .zip(bound_vals)
+        .zip(&literal_binds)
+        .enumerate()
+        .map(|(i, ((body, bound_val), literal_bind))| {
+            let suggest = suggest.as_ref().filter(|_| i == wildcard_idx);
+            generate_arm(i, body, bound_val, literal_bind.as_ref(), suggest)
+        });
+    let enumvalue = (0..bodies.len()).map(|i| format_ident!("V{i}"));
+    let suggestions = suggest.as_ref().map(|SuggestBinding { distance, .. }| {
+        generate_suggestions(&trie, &patterns, *distance, is_str)
+    });
+
+    let is_auto = strategy.unwrap_or_default() == Strategy::Auto;
+    let strategy = match strategy.unwrap_or_default() {
+        // The options of the double-array are only meaningful for it.
+        Strategy::Auto if match_attrs.alphabet_remapping || match_attrs.minimize => {
+            Strategy::DoubleArray
+        }
+        Strategy::Auto => select_strategy(&trie, &patterns),
+        strategy => strategy,
+    };
+    let generate = |strategy| {
+        generate_strategy(
+            strategy,
+            &trie,
+            &patterns,
+            wildcard_idx,
+            &literal_binds,
+            &match_attrs,
+            is_str,
+        )
+    };
+    // The double-array is applicable to any patterns, so a selected strategy falls back to it
+    // instead of failing.
+    let (strategy, (walker, layout)) = iter::once(strategy)
+        .chain(is_auto.then_some(Strategy::DoubleArray))
+        .find_map(|strategy| generate(strategy).map(|generated| (strategy, generated)))
+        .ok_or_else(|| Error::new(expr.span(), ERROR_PERFECT_HASH_NOT_FOUND))?;
+    // Patterns are listed for type inference, but string patterns are not available in const
+    // contexts.
+    let pat_set = if const_compatible { vec![] } else { pat_set };
+    let report = match_attrs
+        .report_strategy
+        .then(|| generate_strategy_report(strategy, layout));
+    let expr = quote! {
+        {
+            #report
+            // A fieldless enum takes the smallest integer that fits the number of arms, so
+            // the value is a single byte for up to 256 arms.
+            #[derive(Clone, Copy)]
+            enum __TrieMatchValue {
+                #( #enumvalue, )*
+            }
+            #suggestions
+            #( #attrs )*
+            match #expr {
+                // This is for type inference.
+                query @ ( #( #pat_set | )* _) => {
+                    match #walker {
+                        #( #arm, )*
+                    }
+                }
+            }
+        }
+    };
+    Ok(Expansion {
+        expr,
+        strategy,
+        layout,
+    })
+}
+
+/// Generates a statement that reports the strategy and the layout of the double-array as a
+/// warning at compile time. Procedural macros cannot emit warnings on stable Rust, so the
+/// statement calls a deprecated function whose note contains the report.
+fn generate_strategy_report(strategy: Strategy, layout: Option<LayoutStats>) -> TokenStream {
+    let name = strategy.name();
+    let note = layout.map_or_else(
+        || format!("`trie_match!` uses the `{name}` strategy"),
+        |layout| {
+            format!(
+                "`trie_match!` uses the `{name}` strategy with {} states in {} slots",
+                layout.num_states, layout.num_slots,
+            )
+        },
+    );
+    quote! {
+        #[deprecated(note = #note)]
+        const fn __trie_match_report_strategy() {}
+        __trie_match_report_strategy();
+    }
+}
+
+/// Generates the expression of the strategy that evaluates to a tuple of the arm, the query, and
+/// the matched literal. The layout of the double-array is also returned if the strategy uses it.
+///
+/// Returns `None` if the strategy is not applicable to the patterns.
+///
+/// # Arguments
+///
+/// * `strategy` - Strategy other than [`Strategy::Auto`].
+/// * `trie` - Trie whose values are indices of `patterns`.
+/// * `patterns` - Tuples of the pattern and the arm index.
+/// * `wildcard_idx` - Arm index of the wildcard.
+/// * `literal_binds` - Literal bindings of arms.
+/// * `match_attrs` - Attributes of the match expression.
+/// * `is_str` - Whether all patterns are string literals.
+fn generate_strategy(
+    strategy: Strategy,
+    trie: &Sparse<usize>,
+    patterns: &[(Vec<u8>, usize)],
+    wildcard_idx: usize,
+    literal_binds: &[Option<LiteralBinding>],
+    match_attrs: &MatchAttributes,
+    is_str: bool,
+) -> Option<(TokenStream, Option<LayoutStats>)> {
+    // The strategies without tables convert the query in the same way as the const walker.
+    let query_ref = match (match_attrs.const_compatible, is_str) {
+        (false, _) => quote! { ::core::convert::AsRef::<[u8]>::as_ref(&query) },
+        (true, true) => quote! { query.as_bytes() },
+        (true, false) => quote! { query },
+    };
+    let with_literals = literal_binds.iter().any(Option::is_some);
+    let walker = match strategy {
+        Strategy::Auto => unreachable!("the strategy is already selected"),
+        Strategy::DoubleArray => {
+            let (walker, layout) = generate_double_array(
+                trie,
+                patterns,
+                wildcard_idx,
+                literal_binds,
+                match_attrs,
+                is_str,
+            );
+            return Some((walker, Some(layout)));
+        }
+        Strategy::Length => {
+            generate_length_dispatch(patterns, wildcard_idx, with_literals, &query_ref)
+        }
+        Strategy::DecisionTree => {
+            generate_decision_tree(patterns, wildcard_idx, with_literals, &query_ref)
+        }
+        Strategy::Match => generate_plain_match(patterns, wildcard_idx, with_literals, &query_ref),
+        Strategy::PerfectHash => generate_perfect_hash(
+            patterns,
+            wildcard_idx,
+            with_literals,
+            &query_ref,
+            match_attrs.const_compatible,
+        )?,
+    };
+    Some((walker, None))
+}
+
+/// Generates a tuple of the arm and the matched literal, which is returned by the strategies
+/// without tables. The literal is `()` if `with_literals` is `false`.
+fn generate_output(bytes: &[u8], arm: usize, with_literals: bool) -> TokenStream {
+    let arm = format_ident!("V{arm}");
+    if with_literals {
+        let lit = LitByteStr::new(bytes, Span::call_site());
+        quote! { (__TrieMatchValue::#arm, #lit as &'static [u8]) }
+    } else {
+        quote! { (__TrieMatchValue::#arm, ()) }
+    }
+}
+
+/// Generates the walker of the double-array built from the trie, and returns it with the layout
+/// of the double-array.
+///
+/// # Arguments
+///
+/// * `trie` - Trie whose values are indices of `patterns`.
+/// * `patterns` - Tuples of the pattern and the arm index.
+/// * `wildcard_idx` - Arm index of the wildcard.
+/// * `literal_binds` - Literal bindings of arms.
+/// * `match_attrs` - Attributes of the match expression.
+/// * `is_str` - Whether all patterns are string literals.
+fn generate_double_array(
+    trie: &Sparse<usize>,
+    patterns: &[(Vec<u8>, usize)],
+    wildcard_idx: usize,
+    literal_binds: &[Option<LiteralBinding>],
+    match_attrs: &MatchAttributes,
+    is_str: bool,
+) -> (TokenStream, LayoutStats) {
+    let options = BuildOptions {
+        tail_compression: true,
+        alphabet_remapping: match_attrs.alphabet_remapping,
+        path_compression: true,
+        // Overridden by `Tables::new`.
+        ..BuildOptions::default()
+    };
+    // Minimization merges subtrees with equal values, so the patterns of an arm share the index
+    // of its first pattern unless the arm binds the literal.
+    let minimized_trie: Option<Sparse<usize>> = match_attrs.minimize.then(|| {
+        let mut first_patterns = vec![None; literal_binds.len()];
+        patterns
+            .iter()
+            .enumerate()
+            .map(|(i, (k, v))| {
+                let first = *first_patterns[*v].get_or_insert(i);
+                (
+                    k,
+                    if literal_binds[*v].is_some() {
+                        i
+                    } else {
+                        first
+                    },
+                )
+            })
+            .collect()
+    });
+    let tables = Tables::new(
+        minimized_trie.as_ref().unwrap_or(trie),
+        options,
+        match_attrs.minimize,
+        patterns,
+        wildcard_idx,
+        literal_binds.iter().any(Option::is_some),
+        !match_attrs.const_compatible,
+    );
+    let walker = if match_attrs.const_compatible {
+        generate_const_walker(&tables, is_str)
+    } else {
+        generate_walker(&tables)
+    };
+    (walker, tables.layout)
+}
+
+/// Tables of the double-array embedded into the generated code.
+struct Tables {
+    /// Integer type of base values.
+    base_type: Ident,
+
+    /// Tuples of the base value, the check value, and the arm of each position.
+    slots: Vec<TokenStream>,
+
+    /// Position of the root.
+    root: usize,
+
+    /// How positions of transitions are kept in bounds.
+    bounds: Bounds,
+
+    /// Expression computing the index of the tail of `base`, or the index of the segment plus
+    /// the number of tails. The index is out of range for other base values.
+    sentinel_index: TokenStream,
+
+    /// Pattern literals of positions, which are only generated if `#[literal(...)]` is used.
+    literals: Option<Vec<LitByteStr>>,
+
+    /// Tables of tails, which are only generated if the double-array has tails.
+    tails: Option<TailTables>,
+
+    /// Chains of states compared a word at a time, and the number of tails.
+    segments: (Vec<Segment>, usize),
+
+    /// Codes of bytes, which are only generated if the alphabet is remapped.
+    codes: Option<Vec<u8>>,
+
+    /// Arm of the wildcard.
+    wildcard: Ident,
+
+    /// Numbers of slots and states of the double-array.
+    layout: LayoutStats,
+}
+
+/// How the walker keeps positions of transitions in bounds.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bounds {
+    /// Positions are checked against the number of slots.
+    Checked,
+
+    /// Positions out of bounds are clamped to the last slot, which is a sentinel whose check
+    /// value no transition from a tail or a segment accepts.
+    Clamped,
+
+    /// The slots are padded so that every base value plus every code is in bounds.
+    Padded,
+}
+
+/// Tables of single-branch suffixes stored outside the double-array.
+struct TailTables {
+    /// Tuples of the start and end offsets in `bytes` and the arm.
+    entries: Vec<TokenStream>,
+
+    /// Concatenated tails.
+    bytes: LitByteStr,
+
+    /// Pattern literals of tails, which are only generated if `#[literal(...)]` is used.
+    literals: Option<Vec<LitByteStr>>,
+}
+
+/// Chooses the smallest signed integer type that represents the base values, and returns the
+/// signed and unsigned types and the converted base values.
+///
+/// Base values of states without children are converted to `MAX` of the type, and those of states
+/// with tails or segments are converted to `MIN` plus the index of the tail or `MIN` plus the
+/// number of tails plus the index of the segment. The type must be large enough that `MAX` is not
+/// less than the number of positions, and that other base values are not mistaken for tails or
+/// segments. The base values reserved for the last states of segments are not stored, but they
+/// must not be mistaken either, since they lead to the check values of the last states.
+fn narrow_bases(
+    bases: &[i32],
+    segment_bases: &[i32],
+    num_tails: usize,
+) -> ((Ident, Ident), Vec<i64>) {
+    let num_tails = i64::try_from(num_tails).unwrap();
+    let len = i64::try_from(bases.len()).unwrap();
+    let tail_index = |base: i32| {
+        let idx = i64::from(base) - i64::from(TAIL_BASE);
+        (idx < num_tails).then_some(idx)
+    };
+    for bits in [8, 16, 32] {
+        let min = -(1_i64 << (bits - 1));
+        let max = (1_i64 << (bits - 1)) - 1;
+        let fits = len <= max
+            && bases.iter().all(|&base| {
+                base == i32::MAX
+                    || tail_index(base).is_some()
+                    || (min + num_tails..max).contains(&i64::from(base))
+            })
+            && segment_bases
+                .iter()
+                .all(|&base| (min + num_tails..max).contains(&i64::from(base)));
+        if fits {
+            let narrowed = bases
+                .iter()
+                .map(|&base| {
+                    if base == i32::MAX {
+                        max
+                    } else {
+                        tail_index(base).map_or_else(|| i64::from(base), |idx| min + idx)
+                    }
+                })
+                .collect();
+            return (
+                (format_ident!("i{bits}"), format_ident!("u{bits}")),
+                narrowed,
+            );
+        }
+    }
+    unreachable!("base values always fit in i32");
+}
+
+/// Builds the double-array with each of [`Placement::ALL`] and returns the one with the fewest
+/// slots. Ties keep the earlier order, so the depth-first order is preferred.
+fn build_densest(
+    trie: &Sparse<usize>,
+    options: BuildOptions,
+    minimize: bool,
+    wildcard_value: usize,
+) -> DoubleArray<usize> {
+    Placement::ALL
+        .into_iter()
+        .map(|placement| {
+            let options = BuildOptions {
+                placement,
+                ..options
+            };
+            if minimize {
+                trie.build_minimized(wildcard_value, options).0
+            } else {
+                trie.build_with_options(wildcard_value, options)
+            }
+        })
+        .min_by_key(|da| da.bases.len())
+        .unwrap()
+}
+
+/// Returns the base values reserved for the last states of segments. Each of them is placed as
+/// the only child of a base value that is not stored in the arrays.
+fn segment_bases(segments: &[Segment], codes: Option<&[u8; 256]>) -> Vec<i32> {
+    segments
+        .iter()
+        .map(|segment| {
+            let last = *segment.bytes.last().unwrap();
+            let code = codes.map_or(last, |codes| codes[usize::from(last)]);
+            i32::try_from(segment.target).unwrap() - i32::from(code)
+        })
+        .collect()
+}
+
+impl Tables {
+    /// Builds the tables of the trie.
+    ///
+    /// # Arguments
+    ///
+    /// * `trie` - Trie whose values are indices of `patterns`.
+    /// * `options` - Options of the double-array.
+    /// * `minimize` - Whether equivalent subtrees are merged.
+    /// * `patterns` - Tuples of the pattern and the arm index.
+    /// * `wildcard_idx` - Arm index of the wildcard.
+    /// * `with_literals` - Whether the literal tables are generated.
+    /// * `unchecked` - Whether the walker can read slots without bounds checks.
+    fn new(
+        trie: &Sparse<usize>,
+        options: BuildOptions,
+        minimize: bool,
+        patterns: &[(Vec<u8>, usize)],
+        wildcard_idx: usize,
+        with_literals: bool,
+        unchecked: bool,
+    ) -> Self {
+        let da = build_densest(trie, options, minimize, patterns.len());
+        let layout = da.layout_stats();
+        let segment_bases = segment_bases(&da.segments, da.codes.as_ref());
+        let padded = unchecked
+            .then(|| Padded::new(&da, &segment_bases))
+            .flatten();
+        let DoubleArray {
+            mut bases,
+            mut checks,
+            values: mut outs,
+            tails,
+            mut segments,
+            codes,
+            wildcard,
+            ..
+        } = da;
+
+        let arm_ident =
+            |out: usize| format_ident!("V{}", patterns.get(out).map_or(wildcard_idx, |p| p.1));
+        let num_sentinels = tails.len() + segments.len();
+        let (base_type, bases, bounds, root, sentinel_index) = if let Some(padded) = padded {
+            for segment in &mut segments {
+                segment.target += padded.root;
+            }
+            let sentinel_index = padded.sentinel_index();
+            checks = padded.checks;
+            outs = padded.values;
+            (
+                padded.base_type,
+                padded.bases,
+                Bounds::Padded,
+                padded.root,
+                sentinel_index,
+            )
+        } else {
+            let sentinel_check = unchecked
+                .then(|| sentinel_check(&tails, &segments, codes.as_ref()))
+                .flatten();
+            let bounds = sentinel_check.map_or(Bounds::Checked, |check| {
+                bases.push(i32::MAX);
+                checks.push(check);
+                outs.push(wildcard);
+                Bounds::Clamped
+            });
+            // Segments follow tails in the range of base values below `MIN`.
+            let ((base_type, unsigned_base_type), bases) =
+                narrow_bases(&bases, &segment_bases, num_sentinels);
+            let sentinel_index =
+                quote! { base.wrapping_sub(#base_type::MIN) as #unsigned_base_type as usize };
+            (base_type, bases, bounds, 0, sentinel_index)
+        };
+        let slots = bases
+            .into_iter()
+            .zip(checks)
+            .zip(&outs)
+            .map(|((base, check), &out)| {
+                let base = Literal::i64_unsuffixed(base);
+                let out = arm_ident(out);
+                quote! { __TrieMatchSlot(#base, #check, __TrieMatchValue::#out) }
+            })
+            .collect();
+        let literal = |out: usize| {
+            let bytes = patterns.get(out).map_or(&[][..], |p| &p.0);
+            LitByteStr::new(bytes, Span::call_site())
+        };
+        let literals = with_literals.then(|| outs.iter().map(|&out| literal(out)).collect());
+
+        let tails = (!tails.is_empty()).then(|| {
+            let mut bytes = vec![];
+            let entries = tails
+                .iter()
+                .map(|(tail, out)| {
+                    let start = u32::try_from(bytes.len()).unwrap();
+                    bytes.extend_from_slice(tail);
+                    let end = u32::try_from(bytes.len()).unwrap();
+                    let out = arm_ident(*out);
+                    quote! { (#start, #end, __TrieMatchValue::#out) }
+                })
+                .collect();
+            TailTables {
+                entries,
+                bytes: LitByteStr::new(&bytes, Span::call_site()),
+                literals: with_literals
+                    .then(|| tails.iter().map(|&(_, out)| literal(out)).collect()),
+            }
+        });
+        let num_tails = tails.as_ref().map_or(0, |tails| tails.entries.len());
+        Self {
+            base_type,
+            slots,
+            root,
+            bounds,
+            sentinel_index,
+            literals,
+            tails,
+            segments: (segments, num_tails),
+            layout,
+            codes: codes.map(Vec::from),
+            wildcard: format_ident!("V{wildcard_idx}"),
+        }
+    }
+}
+
+/// Generates an expression that traverses the double-array and returns a tuple of the arm, the
+/// query, and the matched literal.
+fn generate_walker(tables: &Tables) -> TokenStream {
+    let Tables {
+        base_type,
+        slots,
+        root,
+        bounds,
+        sentinel_index,
+        literals,
+        tails,
+        segments: (segments, num_tails),
+        codes,
+        wildcard,
+        ..
+    } = tables;
+    let (literal_table, literal_wildcard, literal_out) = literals.as_ref().map_or_else(
+        || (quote! {}, quote! { () }, quote! { () }),
+        |literals| {
+            (
+                quote! { let literals: &'static [&'static [u8]] = &[ #( #literals, )* ]; },
+                quote! { &[] as &'static [u8] },
+                quote! { *literals.get_unchecked(pos) },
+            )
+        },
+    );
+    let (tail_table, tail_match) = tails.as_ref().map_or_else(
+        || (quote! {}, quote! {}),
+        |tails| generate_tail_match(tails, sentinel_index),
+    );
+    let segment_match = generate_segment_match(segments, *num_tails, sentinel_index, false);
+    // A segment skips several bytes, so the loop needs the index of the byte.
+    let (loop_head, advance) = if !segments.is_empty() {
+        (
+            quote! {
+                let mut i = 0;
+                while let Some(&b) = query_ref.get(i)
+            },
+            quote! { i += 1; },
+        )
+    } else if tails.is_some() {
+        (
+            quote! { for (i, &b) in query_ref.iter().enumerate() },
+            quote! {},
+        )
+    } else {
+        (quote! { for &b in query_ref }, quote! {})
+    };
+    // If the alphabet is remapped, transitions use the code of each byte instead.
+    let (code_table, code) = codes.as_ref().map_or_else(
+        || (quote! {}, quote! { b }),
+        |codes| {
+            (
+                quote! { let codes: &'static [u8; 256] = &[ #( #codes, )* ]; },
+                quote! { codes[usize::from(b)] },
+            )
+        },
+    );
+    let transition = generate_transition(*bounds, slots.len(), &advance);
+    let root = Literal::usize_unsuffixed(*root);
+    quote! {
+        (|query| unsafe {
+            // Each position is packed into a slot so that a transition reads a single slot.
+            #[derive(Clone, Copy)]
+            #[repr(C, packed)]
+            struct __TrieMatchSlot(#base_type, u8, __TrieMatchValue);
+
+            let query_ref = ::core::convert::AsRef::<[u8]>::as_ref(&query);
+            let slots: &'static [__TrieMatchSlot] = &[ #( #slots, )* ];
+            #literal_table
+            #tail_table
+            #code_table
+            let mut pos = #root;
+            let mut base = slots[#root].0;
+            #loop_head {
+                let c = #code;
+                #transition
+                #segment_match
+                #tail_match
+                return (__TrieMatchValue::#wildcard, query, #literal_wildcard);
+            }
+            (slots.get_unchecked(pos).2, query, #literal_out)
+        })(query)
+    }
+}
+
+/// Generates a statement that moves to the child of the current state with `c` and continues
+/// the loop if the transition exists.
+fn generate_transition(bounds: Bounds, num_slots: usize, advance: &TokenStream) -> TokenStream {
+    match bounds {
+        Bounds::Checked => quote! {
+            pos = i32::from(base).wrapping_add(i32::from(c)) as usize;
+            if let Some(slot) = slots.get(pos) {
+                if slot.1 == c {
+                    base = slot.0;
+                    #advance
+                    continue;
+                }
+            }
+        },
+        Bounds::Clamped | Bounds::Padded => {
+            let pos = if bounds == Bounds::Padded {
+                quote! { base as usize + usize::from(c) }
+            } else {
+                // Negative positions are also clamped since they wrap around.
+                let last = num_slots - 1;
+                quote! { (i32::from(base).wrapping_add(i32::from(c)) as usize).min(#last) }
+            };
+            quote! {
+                pos = #pos;
+                let slot = slots.get_unchecked(pos);
+                if slot.1 == c {
+                    base = slot.0;
+                    #advance
+                    continue;
+                }
+            }
+        }
+    }
+}
+
+/// Generates the same expression as [`generate_walker`] that is available in const contexts.
+///
+/// Closures, traits, iterators, and `get_unchecked` are not used, so the query must be `&str` if
+/// all patterns are string literals, and `&[u8]` otherwise.
+fn generate_const_walker(tables: &Tables, is_str: bool) -> TokenStream {
+    let Tables {
+        base_type,
+        slots,
+        sentinel_index,
+        literals,
+        tails,
+        segments: (segments, num_tails),
+        codes,
+        wildcard,
+        ..
+    } = tables;
+    let segment_match = generate_segment_match(segments, *num_tails, sentinel_index, true);
+    let (literal_table, literal_wildcard, literal_out) = literals.as_ref().map_or_else(
+        || (quote! {}, quote! { () }, quote! { () }),
+        |literals| {
+            (
+                quote! { const LITERALS: &[&[u8]] = &[ #( #literals, )* ]; },
+                quote! { &[] as &'static [u8] },
+                quote! { LITERALS[pos] },
+            )
+        },
+    );
+    let (tail_table, tail_match, tail_out) = tails.as_ref().map_or_else(
+        || (quote! {}, quote! {}, quote! {}),
+        |tails| generate_const_tail_match(tails, sentinel_index),
+    );
+    let query_ref = if is_str {
+        quote! { query.as_bytes() }
+    } else {
+        quote! { query }
+    };
+    let (code_table, code) = codes.as_ref().map_or_else(
+        || (quote! {}, quote! { b }),
+        |codes| {
+            (
+                quote! { const CODES: &[u8; 256] = &[ #( #codes, )* ]; },
+                quote! { CODES[b as usize] },
+            )
+        },
+    );
+    quote! {
+        {
+            #[derive(Clone, Copy)]
+            #[repr(C, packed)]
+            struct __TrieMatchSlot(#base_type, u8, __TrieMatchValue);
+
+            const SLOTS: &[__TrieMatchSlot] = &[ #( #slots, )* ];
+            #literal_table
+            #tail_table
+            #code_table
+            let query_ref: &[u8] = #query_ref;
+            let mut pos = 0;
+            let mut base = SLOTS[0].0;
+            let mut i = 0;
+            let mut matched = true;
+            while i < query_ref.len() {
+                let b = query_ref[i];
+                let c = #code;
+                pos = (base as i32).wrapping_add(c as i32) as usize;
+                if pos >= SLOTS.len() || SLOTS[pos].1 != c {
+                    #segment_match
+                    matched = false;
+                    #tail_match
+                    break;
+                }
+                base = SLOTS[pos].0;
+                i += 1;
+            }
+            if matched {
+                (SLOTS[pos].2, query, #literal_out)
+            } #tail_out else {
+                (__TrieMatchValue::#wildcard, query, #literal_wildcard)
+            }
+        }
+    }
+}
+
+/// Generates a statement that skips the segment of the current state and continues the loop if the
+/// rest of the query starts with the segment.
+///
+/// The index of the segment is the sentinel index of `base` minus `num_tails`, which is out of
+/// range for states without segments. Each segment is compared in the chunks decided by the
+/// builder, by loading a word of the query and comparing it with a constant word. The words are
+/// in the native byte order, so the comparison works on any target. If `const_compatible` is
+/// `true`, the words are built from bytes instead of unaligned loads.
+fn generate_segment_match(
+    segments: &[Segment],
+    num_tails: usize,
+    sentinel_index: &TokenStream,
+    const_compatible: bool,
+) -> TokenStream {
+    if segments.is_empty() {
+        return quote! {};
+    }
+    let slot_base = if const_compatible {
+        quote! { SLOTS[pos].0 }
+    } else {
+        quote! { slots.get_unchecked(pos).0 }
+    };
+    let arms = segments.iter().enumerate().map(|(j, segment)| {
+        let len = segment.bytes.len();
+        let target = segment.target;
+        let words = segment.chunks.iter().map(|&(offset, width)| {
+            let word_type = format_ident!("u{}", width * 8);
+            let lit = LitByteStr::new(&segment.bytes[offset..offset + width], Span::call_site());
+            let word = if const_compatible {
+                let bytes = (offset..offset + width).map(|k| quote! { query_ref[i + #k] });
+                quote! { #word_type::from_ne_bytes([ #( #bytes, )* ]) }
+            } else {
+                quote! {
+                    query_ref.as_ptr().add(i + #offset).cast::<#word_type>().read_unaligned()
+                }
+            };
+            quote! { #word == #word_type::from_ne_bytes(*#lit) }
+        });
+        quote! {
+            #j => {
+                if query_ref.len() - i >= #len #( && #words )* {
+                    pos = #target;
+                    base = #slot_base;
+                    i += #len;
+                    continue;
+                }
+            }
+        }
+    });
+    quote! {
+        let sentinel = #sentinel_index;
+        match sentinel.wrapping_sub(#num_tails) {
+            #( #arms, )*
+            _ => {}
+        }
+    }
+}
+
+/// Generates the tail tables and a statement that returns the arm of the tail if the rest of the
+/// query equals the tail of the current state.
+///
+/// The index of the tail is the sentinel index of `base`, which is out of range for states
+/// without tails.
+fn generate_tail_match(
+    tails: &TailTables,
+    sentinel_index: &TokenStream,
+) -> (TokenStream, TokenStream) {
+    let TailTables {
+        entries,
+        bytes,
+        literals,
+    } = tails;
+    let (tail_literal_table, tail_literal_out) = literals.as_ref().map_or_else(
+        || (quote! {}, quote! { () }),
+        |literals| {
+            (
+                quote! {
+                    let tail_literals: &'static [&'static [u8]] = &[ #( #literals, )* ];
+                },
+                quote! { *tail_literals.get_unchecked(tail) },
+            )
+        },
+    );
+    (
+        quote! {
+            let tails: &'static [(u32, u32, __TrieMatchValue)] = &[ #( #entries, )* ];
+            let tail_bytes: &'static [u8] = #bytes;
+            #tail_literal_table
+        },
+        quote! {
+            let tail = #sentinel_index;
+            if let Some(&(start, end, out)) = tails.get(tail) {
+                if query_ref.get_unchecked(i..)
+                    == tail_bytes.get_unchecked(start as usize..end as usize)
+                {
+                    return (out, query, #tail_literal_out);
+                }
+            }
+        },
+    )
+}
+
+/// Generates the same tail match as [`generate_tail_match`] that is available in const contexts.
+///
+/// The returned statement sets `tail` to the index of the tail that equals the rest of the query,
+/// and the returned `else if` branch evaluates to the arm of the tail.
+fn generate_const_tail_match(
+    tails: &TailTables,
+    sentinel_index: &TokenStream,
+) -> (TokenStream, TokenStream, TokenStream) {
+    let TailTables {
+        entries,
+        bytes,
+        literals,
+    } = tails;
+    let (tail_literal_table, tail_literal_out) = literals.as_ref().map_or_else(
+        || (quote! {}, quote! { () }),
+        |literals| {
+            (
+                quote! { const TAIL_LITERALS: &[&[u8]] = &[ #( #literals, )* ]; },
+                quote! { TAIL_LITERALS[tail] },
+            )
+        },
+    );
+    (
+        quote! {
+            const TAILS: &[(u32, u32, __TrieMatchValue)] = &[ #( #entries, )* ];
+            const TAIL_BYTES: &[u8] = #bytes;
+            #tail_literal_table
+            let mut tail = usize::MAX;
+        },
+        quote! {
+            let t = #sentinel_index;
+            if t < TAILS.len() {
+                let start = TAILS[t].0 as usize;
+                let len = TAILS[t].1 as usize - start;
+                if len == query_ref.len() - i {
+                    let mut j = 0;
+                    while j < len && TAIL_BYTES[start + j] == query_ref[i + j] {
+                        j += 1;
+                    }
+                    if j == len {
+                        tail = t;
+                    }
+                }
+            }
+        },
+        quote! {
+            else if tail < TAILS.len() {
+                (TAILS[tail].2, query, #tail_literal_out)
+            }
+        },
+    )
+}
@@ -0,0 +1,190 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use trie_match_core::{DoubleArray, Segment, TAIL_BASE};
+
+/// Padding is added only if the padded arrays have at most this many slots per slot of the
+/// double-array, since the padding slots also occupy the cache.
+const MAX_PADDED_SLOTS_RATIO: f64 = 1.25;
+
+/// Arrays of the double-array padded so that every transition reads a slot in bounds.
+///
+/// Positions are shifted by [`root`](Self::root) so that all base values are non-negative. Each
+/// state without children in the arrays takes a sentinel base value, which is
+/// [`sentinel_start`](Self::sentinel_start) plus [`step`](Self::step) times the index of the
+/// tail, the index of the segment after the tails, or the number of tails and segments for the
+/// other states. The sentinel values are greater than the base values reserved by the builder,
+/// including those of the last states of segments and 0 reserved for the root, so a transition
+/// from a sentinel value only matches the check of an unused slot, whose check is changed so that
+/// it does not match either. The arrays are extended so that every base value plus every code is
+/// in bounds.
+pub struct Padded {
+    /// Unsigned integer type of base values.
+    pub base_type: Ident,
+
+    /// Base values.
+    pub bases: Vec<i64>,
+
+    /// Check values.
+    pub checks: Vec<u8>,
+
+    /// Values of positions. Padding slots have the wildcard value.
+    pub values: Vec<usize>,
+
+    /// Position of the root.
+    pub root: usize,
+
+    /// Sentinel value of the first tail.
+    pub sentinel_start: usize,
+
+    /// Difference between consecutive sentinel values.
+    pub step: usize,
+}
+
+impl Padded {
+    /// Pads the arrays, or returns `None` if the padding is larger than
+    /// [`MAX_PADDED_SLOTS_RATIO`] allows or a used slot is reachable from a sentinel value.
+    ///
+    /// # Arguments
+    ///
+    /// * `da` - Double-array to be padded.
+    /// * `segment_bases` - Base values reserved for the last states of segments, which are not
+    ///   stored in `da.bases`.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(da: &DoubleArray<usize>, segment_bases: &[i32]) -> Option<Self> {
+        let num_sentinels = da.tails.len() + da.segments.len();
+        let max_code = da
+            .codes
+            .map_or(u8::MAX, |codes| codes.into_iter().max().unwrap());
+        let is_state_base = |base: i32| {
+            base != i32::MAX
+                && i64::from(base) - i64::from(TAIL_BASE) >= i64::try_from(num_sentinels).unwrap()
+        };
+        let reserved_bases = da
+            .bases
+            .iter()
+            .copied()
+            .filter(|&base| is_state_base(base))
+            .chain(segment_bases.iter().copied())
+            .chain([0]);
+        let min_base = reserved_bases.clone().min().unwrap();
+        let max_base = reserved_bases.max().unwrap();
+        // Base values are greater than `-256`, so the shift is less than 256.
+        let shift = -i64::from(min_base.min(0));
+        let root = usize::try_from(shift).unwrap();
+        let sentinel_start = usize::try_from(i64::from(max_base) + 1 + shift).unwrap();
+        // If no check equals `u8::MAX`, sentinel values can be consecutive, and checks of unused
+        // slots are changed to `u8::MAX`. Otherwise, sentinel values are even offsets from the
+        // start, and checks of unused slots are changed to make odd offsets.
+        let step = if max_code < u8::MAX { 1 } else { 2 };
+        let max_sentinel = sentinel_start + step * num_sentinels;
+        let num_slots = da.bases.len();
+        let len = (root + num_slots).max(max_sentinel + usize::from(max_code) + 1);
+        if len as f64 > num_slots as f64 * MAX_PADDED_SLOTS_RATIO {
+            return None;
+        }
+
+        let base_type = if u8::try_from(max_sentinel).is_ok() {
+            format_ident!("u8")
+        } else if u16::try_from(max_sentinel).is_ok() {
+            format_ident!("u16")
+        } else {
+            format_ident!("u32")
+        };
+        let sentinel_index = |base: i32| {
+            let idx = usize::try_from(i64::from(base) - i64::from(TAIL_BASE)).unwrap();
+            sentinel_start + step * idx
+        };
+        let mut is_used = vec![false; len];
+        for &pos in da.positions.iter().filter(|&&pos| pos != usize::MAX) {
+            is_used[root + pos] = true;
+        }
+        let mut padded_bases = vec![max_sentinel; len];
+        let mut padded_checks = vec![0; len];
+        let mut padded_values = vec![da.wildcard; len];
+        for (pos, (&base, &check)) in da.bases.iter().zip(&da.checks).enumerate() {
+            padded_bases[root + pos] = if base == i32::MAX {
+                max_sentinel
+            } else if is_state_base(base) {
+                usize::try_from(i64::from(base) + shift).unwrap()
+            } else {
+                sentinel_index(base)
+            };
+            padded_checks[root + pos] = check;
+            padded_values[root + pos] = da.values[pos];
+        }
+        for (pos, check) in padded_checks.iter_mut().enumerate().skip(sentinel_start) {
+            let Some(offset) = pos
+                .checked_sub(usize::from(*check))
+                .and_then(|parent| parent.checked_sub(sentinel_start))
+            else {
+                continue;
+            };
+            if offset % step != 0 {
+                continue;
+            }
+            // The check of a used slot leads back to a reserved base value, which is less than
+            // the sentinel values.
+            if is_used[pos] {
+                return None;
+            }
+            *check = if step == 1 {
+                u8::MAX
+            } else {
+                // The offset from the start becomes odd.
+                u8::try_from((pos - sentinel_start + 1) % 256).unwrap()
+            };
+        }
+        Some(Self {
+            base_type,
+            bases: padded_bases
+                .into_iter()
+                .map(|base| i64::try_from(base).unwrap())
+                .collect(),
+            checks: padded_checks,
+            values: padded_values,
+            root,
+            sentinel_start,
+            step,
+        })
+    }
+
+    /// Returns an expression computing the index of the tail of `base`, or the index of the
+    /// segment plus the number of tails.
+    pub fn sentinel_index(&self) -> TokenStream {
+        let start = self.sentinel_start;
+        if self.step == 1 {
+            quote! { (base as usize).wrapping_sub(#start) }
+        } else {
+            let step = self.step;
+            quote! { (base as usize).wrapping_sub(#start) / #step }
+        }
+    }
+}
+
+/// Returns the check value of a sentinel slot that out-of-bounds positions are clamped to, or
+/// `None` if no value is available.
+///
+/// A transition into the sentinel slot leads to a state without children and values, which only
+/// changes the result if the transition skips the tail or the segment of a state. Therefore, the
+/// check value must not be the first code of any tail or segment.
+///
+/// # Arguments
+///
+/// * `tails` - Tails of the double-array.
+/// * `segments` - Segments of the double-array.
+/// * `codes` - Codes of bytes if the alphabet is remapped.
+pub fn sentinel_check<T>(
+    tails: &[(Vec<u8>, T)],
+    segments: &[Segment],
+    codes: Option<&[u8; 256]>,
+) -> Option<u8> {
+    let mut is_first = [false; 256];
+    let firsts = tails
+        .iter()
+        .map(|(tail, _)| tail[0])
+        .chain(segments.iter().map(|segment| segment.bytes[0]));
+    for b in firsts {
+        is_first[usize::from(codes.map_or(b, |codes| codes[usize::from(b)]))] = true;
+    }
+    (0..=u8::MAX).rev().find(|&c| !is_first[usize::from(c)])
+}
@@ -0,0 +1,277 @@
+use std::cmp::Reverse;
+
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::LitByteStr;
+
+use crate::generate_output;
+
+/// Average number of patterns in a bucket of the first-level hash.
+const BUCKET_SIZE: u32 = 4;
+
+/// Multiplier of the hash function.
+const HASH_MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Multiplier applied after the displacement is mixed into the lower half of the hash, so that
+/// the displacement also changes the upper bits selecting the index.
+const INDEX_MULTIPLIER: u32 = 0x9e37_79b1;
+
+/// Number of displacements tried for a bucket per entry of the table before giving up on the
+/// seed. A displacement maps a pattern to an almost uniformly random index, so the search rarely
+/// fails unless two patterns are inseparable with the seed.
+const MAX_DISPLACEMENTS_PER_ENTRY: u64 = 64;
+
+/// Seed tried first. The following seeds are obtained by adding [`HASH_MULTIPLIER`], so the
+/// generated code is reproducible.
+const INITIAL_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// Maximum number of seeds tried before giving up on the perfect hash.
+const MAX_SEEDS: usize = 64;
+
+/// Hashes the bytes with the seed.
+///
+/// The bytes are read eight bytes at a time, and the last word overlaps with the previous one.
+/// Bytes shorter than a word are read as two overlapping 4-byte words, or as the first, middle,
+/// and last bytes, so the words determine the bytes together with the length. The expression
+/// generated by [`generate_hash`] must compute the same value.
+fn hash(seed: u64, bytes: &[u8]) -> u64 {
+    let len = bytes.len();
+    let mut h = seed ^ (len as u64).wrapping_mul(HASH_MULTIPLIER);
+    let word = if len >= 8 {
+        for chunk in bytes[..len - 1].chunks_exact(8) {
+            let word = u64::from_le_bytes(chunk.try_into().unwrap());
+            h = (h ^ word).wrapping_mul(HASH_MULTIPLIER).rotate_left(29);
+        }
+        u64::from_le_bytes(bytes[len - 8..].try_into().unwrap())
+    } else if len >= 4 {
+        let lo = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+        let hi = u32::from_le_bytes(bytes[len - 4..].try_into().unwrap());
+        u64::from(lo) | (u64::from(hi) << 32)
+    } else if len > 0 {
+        u64::from(bytes[0]) | (u64::from(bytes[len / 2]) << 8) | (u64::from(bytes[len - 1]) << 16)
+    } else {
+        0
+    };
+    h = (h ^ word).wrapping_mul(HASH_MULTIPLIER);
+    h ^ (h >> 32)
+}
+
+/// Maps the hash to a number less than `n` by a multiplication instead of a division.
+#[allow(clippy::cast_possible_truncation)]
+const fn reduce(h: u32, n: u32) -> u32 {
+    ((h as u64 * n as u64) >> 32) as u32
+}
+
+/// Returns the index of the hash in the table of the given length with the displacement.
+#[allow(clippy::cast_possible_truncation)]
+const fn index(h: u64, displacement: u32, len: u32) -> u32 {
+    reduce(
+        (h as u32 ^ displacement).wrapping_mul(INDEX_MULTIPLIER),
+        len,
+    )
+}
+
+/// Perfect hash function mapping patterns to distinct indices.
+struct PerfectHash {
+    /// Seed of the hash function.
+    seed: u64,
+
+    /// Displacements of the buckets.
+    displacements: Vec<u32>,
+
+    /// Pattern indices ordered by their hash indices.
+    order: Vec<usize>,
+}
+
+impl PerfectHash {
+    /// Finds a perfect hash function of the patterns by the hash-and-displace algorithm.
+    ///
+    /// Returns `None` if no seed yields a perfect hash function.
+    fn new(patterns: &[(Vec<u8>, usize)]) -> Option<Self> {
+        let mut seed = INITIAL_SEED;
+        for _ in 0..MAX_SEEDS {
+            let hashes: Vec<u64> = patterns.iter().map(|(p, _)| hash(seed, p)).collect();
+            if let Some((displacements, order)) = Self::displace(&hashes) {
+                return Some(Self {
+                    seed,
+                    displacements,
+                    order,
+                });
+            }
+            seed = seed.wrapping_add(HASH_MULTIPLIER);
+        }
+        None
+    }
+
+    /// Searches displacements mapping the hashes to distinct indices.
+    ///
+    /// The hashes are distributed into buckets by their upper halves. Starting from the largest
+    /// bucket, a displacement is searched for each bucket so that its hashes are mapped to free
+    /// indices.
+    #[allow(clippy::cast_possible_truncation)]
+    fn displace(hashes: &[u64]) -> Option<(Vec<u32>, Vec<usize>)> {
+        let len = u32::try_from(hashes.len()).ok()?;
+        let num_buckets = (len + BUCKET_SIZE - 1) / BUCKET_SIZE;
+        let mut buckets = vec![vec![]; num_buckets as usize];
+        for (i, &h) in hashes.iter().enumerate() {
+            buckets[reduce((h >> 32) as u32, num_buckets) as usize].push(i);
+        }
+        let mut bucket_order: Vec<usize> = (0..buckets.len()).collect();
+        bucket_order.sort_by_key(|&b| Reverse(buckets[b].len()));
+
+        let mut displacements = vec![0; buckets.len()];
+        let mut order = vec![usize::MAX; hashes.len()];
+        let mut indices = vec![];
+        'bucket: for b in bucket_order {
+            for k in 0..u64::from(len) * MAX_DISPLACEMENTS_PER_ENTRY {
+                // Displacements are spread over all bits so that they move the indices.
+                let displacement = (k as u32).wrapping_mul(HASH_MULTIPLIER as u32);
+                indices.clear();
+                for &i in &buckets[b] {
+                    let idx = index(hashes[i], displacement, len) as usize;
+                    if order[idx] != usize::MAX || indices.contains(&idx) {
+                        break;
+                    }
+                    indices.push(idx);
+                }
+                if indices.len() == buckets[b].len() {
+                    for (&idx, &i) in indices.iter().zip(&buckets[b]) {
+                        order[idx] = i;
+                    }
+                    displacements[b] = displacement;
+                    continue 'bucket;
+                }
+            }
+            return None;
+        }
+        Some((displacements, order))
+    }
+}
+
+/// Generates an expression computing [`hash`] of `query_ref` with the seed. Only operations
+/// available in const contexts are used.
+fn generate_hash(seed: u64) -> TokenStream {
+    let read_u64 = |i: TokenStream| {
+        let bytes = (0..8_usize).map(|j| quote! { query_ref[#i + #j] });
+        quote! { u64::from_le_bytes([ #( #bytes, )* ]) }
+    };
+    let read_u32 = |i: TokenStream| {
+        let bytes = (0..4_usize).map(|j| quote! { query_ref[#i + #j] });
+        quote! { (u32::from_le_bytes([ #( #bytes, )* ]) as u64) }
+    };
+    let word = read_u64(quote! { i });
+    let last_word = read_u64(quote! { len - 8 });
+    let lo = read_u32(quote! { 0 });
+    let hi = read_u32(quote! { len - 4 });
+    quote! {
+        {
+            let len = query_ref.len();
+            let mut h = #seed ^ (len as u64).wrapping_mul(#HASH_MULTIPLIER);
+            let word = if len >= 8 {
+                let mut i = 0;
+                while i + 8 < len {
+                    h = (h ^ #word).wrapping_mul(#HASH_MULTIPLIER).rotate_left(29);
+                    i += 8;
+                }
+                #last_word
+            } else if len >= 4 {
+                #lo | (#hi << 32)
+            } else if len > 0 {
+                query_ref[0] as u64
+                    | (query_ref[len / 2] as u64) << 8
+                    | (query_ref[len - 1] as u64) << 16
+            } else {
+                0
+            };
+            h = (h ^ word).wrapping_mul(#HASH_MULTIPLIER);
+            h ^ (h >> 32)
+        }
+    }
+}
+
+/// Generates an expression that evaluates to a tuple of the arm, the query, and the matched
+/// literal in the same way as the double-array walkers.
+///
+/// The expression hashes the query with a perfect hash function of the patterns found at
+/// compile time, and then compares the query with the only pattern that has the same hash
+/// index. The expression is available in const contexts if `const_compatible` is `true`.
+///
+/// Returns `None` if no perfect hash function is found.
+///
+/// # Arguments
+///
+/// * `patterns` - Tuples of the pattern and the arm index.
+/// * `wildcard_idx` - Arm index of the wildcard.
+/// * `with_literals` - Whether the matched literal is returned.
+/// * `query_ref` - Expression converting `query` into `&[u8]`.
+/// * `const_compatible` - Whether the expression must be available in const contexts.
+pub fn generate_perfect_hash(
+    patterns: &[(Vec<u8>, usize)],
+    wildcard_idx: usize,
+    with_literals: bool,
+    query_ref: &TokenStream,
+    const_compatible: bool,
+) -> Option<TokenStream> {
+    let wildcard = generate_output(&[], wildcard_idx, with_literals);
+    if patterns.is_empty() {
+        return Some(quote! {
+            {
+                let (value, literal) = #wildcard;
+                (value, query, literal)
+            }
+        });
+    }
+    let PerfectHash {
+        seed,
+        displacements,
+        order,
+    } = PerfectHash::new(patterns)?;
+    let len = Literal::usize_unsuffixed(patterns.len());
+    let num_buckets = Literal::usize_unsuffixed(displacements.len());
+    let displacements = displacements.into_iter().map(Literal::u32_unsuffixed);
+    let entries = order.iter().map(|&i| {
+        let (bytes, arm) = &patterns[i];
+        let lit = LitByteStr::new(bytes, Span::call_site());
+        let arm = format_ident!("V{arm}");
+        quote! { (#lit, __TrieMatchValue::#arm) }
+    });
+    let hash = generate_hash(seed);
+    let equals = if const_compatible {
+        // Slices cannot be compared in const contexts.
+        quote! {
+            {
+                let mut i = 0;
+                if entry.0.len() == query_ref.len() {
+                    while i < query_ref.len() && entry.0[i] == query_ref[i] {
+                        i += 1;
+                    }
+                }
+                i == query_ref.len() && entry.0.len() == query_ref.len()
+            }
+        }
+    } else {
+        quote! { entry.0 == query_ref }
+    };
+    let out = if with_literals {
+        quote! { (entry.1, entry.0 as &'static [u8]) }
+    } else {
+        quote! { (entry.1, ()) }
+    };
+    Some(quote! {
+        {
+            const DISPLACEMENTS: &[u32] = &[ #( #displacements, )* ];
+            const ENTRIES: &[(&[u8], __TrieMatchValue)] = &[ #( #entries, )* ];
+            let query_ref: &[u8] = #query_ref;
+            let h: u64 = #hash;
+            let displacement = DISPLACEMENTS[(((h >> 32) * #num_buckets) >> 32) as usize];
+            let f = (h as u32 ^ displacement).wrapping_mul(#INDEX_MULTIPLIER);
+            let entry = &ENTRIES[((f as u64 * #len) >> 32) as usize];
+            let (value, literal) = if #equals {
+                #out
+            } else {
+                #wildcard
+            };
+            (value, query, literal)
+        }
+    })
+}
@@ -0,0 +1,41 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::LitByteStr;
+
+use crate::generate_output;
+
+/// Generates an expression that evaluates to a tuple of the arm, the query, and the matched
+/// literal in the same way as the double-array walkers.
+///
+/// The expression is a normal `match` expression on the bytes of the query, which the compiler
+/// expands by itself. The expression is available in const contexts.
+///
+/// # Arguments
+///
+/// * `patterns` - Tuples of the pattern and the arm index.
+/// * `wildcard_idx` - Arm index of the wildcard.
+/// * `with_literals` - Whether the matched literal is returned.
+/// * `query_ref` - Expression converting `query` into `&[u8]`.
+pub fn generate_plain_match(
+    patterns: &[(Vec<u8>, usize)],
+    wildcard_idx: usize,
+    with_literals: bool,
+    query_ref: &TokenStream,
+) -> TokenStream {
+    let wildcard = generate_output(&[], wildcard_idx, with_literals);
+    let arms = patterns.iter().map(|(bytes, arm)| {
+        let lit = LitByteStr::new(bytes, Span::call_site());
+        let out = generate_output(bytes, *arm, with_literals);
+        quote! { #lit => #out }
+    });
+    quote! {
+        {
+            let query_ref: &[u8] = #query_ref;
+            let (value, literal) = match query_ref {
+                #( #arms, )*
+                _ => #wildcard,
+            };
+            (value, query, literal)
+        }
+    }
+}
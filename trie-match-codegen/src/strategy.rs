@@ -0,0 +1,72 @@
+use proc_macro2::Ident;
+use syn::{
+    ext::IdentExt,
+    parse::{Parse, ParseStream},
+    Error,
+};
+
+static ERROR_UNKNOWN_STRATEGY: &str =
+    "unknown strategy: expected `auto`, `double_array`, `length`, `decision_tree`, `match`, or `perfect_hash`";
+
+/// Code generation backend selected by the `#[strategy(...)]` attribute.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Selects one of the other strategies by estimating their costs from the patterns. This is
+    /// the default.
+    #[default]
+    Auto,
+
+    /// Traverses a compact double-array byte by byte.
+    DoubleArray,
+
+    /// Dispatches on the length of the query, and then compares the query with the patterns of
+    /// the same length. This is faster if most patterns have distinct lengths.
+    Length,
+
+    /// Expands into nested `match`es on bytes of the query without tables. This is faster for
+    /// small pattern sets.
+    DecisionTree,
+
+    /// Expands into a normal `match` expression, which the compiler expands by itself.
+    Match,
+
+    /// Hashes the query with a perfect hash function of the patterns, and then compares the query
+    /// with the only candidate. This is suited to large pattern sets sharing few prefixes.
+    ///
+    /// The expansion fails if no perfect hash function is found. If [`Auto`](Self::Auto) selects
+    /// this strategy, [`DoubleArray`](Self::DoubleArray) is used instead.
+    PerfectHash,
+}
+
+impl Strategy {
+    /// Returns the name used in the `#[strategy(...)]` attribute.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::DoubleArray => "double_array",
+            Self::Length => "length",
+            Self::DecisionTree => "decision_tree",
+            Self::Match => "match",
+            Self::PerfectHash => "perfect_hash",
+        }
+    }
+}
+
+impl Parse for Strategy {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        // `match` is a keyword.
+        let ident = input.call(Ident::parse_any)?;
+        [
+            Self::Auto,
+            Self::DoubleArray,
+            Self::Length,
+            Self::DecisionTree,
+            Self::Match,
+            Self::PerfectHash,
+        ]
+        .into_iter()
+        .find(|strategy| ident == strategy.name())
+        .ok_or_else(|| Error::new(ident.span(), ERROR_UNKNOWN_STRATEGY))
+    }
+}
@@ -0,0 +1,179 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, LitByteStr, LitInt, Token,
+};
+use trie_match_core::Sparse;
+
+static ERROR_UNEXPECTED_SUGGEST_OPTION: &str = "expected `distance`";
+static ERROR_DISTANCE_TOO_LARGE: &str = "`distance` must be less than 255";
+
+/// The maximum edit distance used if `distance` is omitted.
+const DEFAULT_DISTANCE: u8 = 2;
+
+/// Arguments of the `#[suggest(...)]` attribute.
+pub struct SuggestBinding {
+    /// Identifier bound to the iterator over similar patterns.
+    pub ident: Ident,
+
+    /// The maximum edit distance.
+    pub distance: u8,
+}
+
+impl Parse for SuggestBinding {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let ident = input.parse()?;
+        let mut distance = DEFAULT_DISTANCE;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key != "distance" {
+                return Err(Error::new(key.span(), ERROR_UNEXPECTED_SUGGEST_OPTION));
+            }
+            input.parse::<Token![=]>()?;
+            let lit: LitInt = input.parse()?;
+            distance = lit.base10_parse()?;
+            if distance == u8::MAX {
+                return Err(Error::new(lit.span(), ERROR_DISTANCE_TOO_LARGE));
+            }
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(Self { ident, distance })
+    }
+}
+
+/// Generates `__TrieMatchSuggestions`, an iterator over patterns within the given edit distance
+/// from a query.
+///
+/// The iterator traverses the trie in depth-first order while computing a row of the
+/// Levenshtein distance table for each state. Subtrees are skipped when all values in the row
+/// exceed the maximum distance, so only a small part of the trie is visited. Each row only keeps
+/// the band of cells around the diagonal, so the iterator holds `(2 * distance + 1)` bytes for
+/// each byte of the longest pattern.
+///
+/// # Arguments
+///
+/// * `trie` - A trie that stores indices of `patterns`.
+/// * `patterns` - Pattern bytes.
+/// * `distance` - The maximum edit distance.
+/// * `is_str` - If `true`, the iterator yields `&'static str`, otherwise `&'static [u8]`.
+pub fn generate_suggestions(
+    trie: &Sparse<usize>,
+    patterns: &[(Vec<u8>, usize)],
+    distance: u8,
+    is_str: bool,
+) -> TokenStream {
+    let max_len = patterns.iter().map(|p| p.0.len()).max().unwrap_or(0);
+    // Queries longer than `max_len + distance` have no similar patterns.
+    let max_query_len = max_len + usize::from(distance);
+    let depth = max_len + 1;
+    // Only cells within `distance` of the diagonal can be at most `distance`, so each row stores
+    // the band of `2 * distance + 1` cells around the diagonal. The `k`-th cell of the row of
+    // depth `i` is the column `i + k - distance`, and cells outside the table are `limit`.
+    let band = 2 * usize::from(distance) + 1;
+    let offset = usize::from(distance);
+    let limit = distance + 1;
+
+    let node = trie
+        .preorder()
+        .into_iter()
+        .map(|(label, depth, end, value)| {
+            let pattern = value.map_or_else(
+                || quote! { ::core::option::Option::None },
+                |&v| {
+                    let bytes = LitByteStr::new(&patterns[v].0, Span::call_site());
+                    quote! { ::core::option::Option::Some(#bytes) }
+                },
+            );
+            quote! { (#label, #depth, #end, #pattern) }
+        });
+    let (item_type, item) = if is_str {
+        (
+            quote! { &'static str },
+            // Safety: All patterns are string literals.
+            quote! { unsafe { ::core::str::from_utf8_unchecked(pattern) } },
+        )
+    } else {
+        (quote! { &'static [u8] }, quote! { pattern })
+    };
+
+    quote! {
+        struct __TrieMatchSuggestions<'a> {
+            query: &'a [u8],
+            node: usize,
+            rows: [[u8; #band]; #depth],
+        }
+
+        impl<'a> __TrieMatchSuggestions<'a> {
+            fn new(query: &'a [u8]) -> Self {
+                let node = if query.len() <= #max_query_len { 0 } else { usize::MAX };
+                Self {
+                    query,
+                    node,
+                    rows: [[0; #band]; #depth],
+                }
+            }
+        }
+
+        impl<'a> ::core::iter::Iterator for __TrieMatchSuggestions<'a> {
+            type Item = (#item_type, usize);
+
+            fn next(&mut self) -> ::core::option::Option<Self::Item> {
+                // Tuples of a label, a depth, an index next to the last descendant, and a pattern.
+                static NODES: &[(u8, usize, usize, ::core::option::Option<&[u8]>)] = &[
+                    #( #node, )*
+                ];
+                while let ::core::option::Option::Some(&(label, depth, end, pattern)) =
+                    NODES.get(self.node)
+                {
+                    let (prev, cur) = self.rows.split_at_mut(depth);
+                    let prev = prev.last();
+                    let cur = &mut cur[0];
+                    let mut row_min = #limit;
+                    for k in 0..#band {
+                        let d = match (depth + k).checked_sub(#offset) {
+                            ::core::option::Option::Some(j) if j <= self.query.len() => {
+                                if let ::core::option::Option::Some(prev) = prev {
+                                    // The cells of the column `j` and `j - 1` of the previous
+                                    // row are the `k + 1`-th and the `k`-th cells.
+                                    let up = prev.get(k + 1).copied().unwrap_or(#limit);
+                                    let left = if k == 0 { #limit } else { cur[k - 1] };
+                                    let diag = if j == 0 {
+                                        #limit
+                                    } else {
+                                        prev[k].saturating_add(u8::from(self.query[j - 1] != label))
+                                    };
+                                    up.saturating_add(1)
+                                        .min(left.saturating_add(1))
+                                        .min(diag)
+                                        .min(#limit)
+                                } else {
+                                    u8::try_from(j).unwrap_or(u8::MAX).min(#limit)
+                                }
+                            }
+                            _ => #limit,
+                        };
+                        cur[k] = d;
+                        row_min = row_min.min(d);
+                    }
+                    if row_min > #distance {
+                        self.node = end;
+                        continue;
+                    }
+                    self.node += 1;
+                    if let ::core::option::Option::Some(pattern) = pattern {
+                        let d = (self.query.len() + #offset)
+                            .checked_sub(depth)
+                            .and_then(|k| cur.get(k))
+                            .copied()
+                            .unwrap_or(#limit);
+                        if d <= #distance {
+                            return ::core::option::Option::Some((#item, usize::from(d)));
+                        }
+                    }
+                }
+                ::core::option::Option::None
+            }
+        }
+    }
+}
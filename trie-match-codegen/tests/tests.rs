@@ -0,0 +1,539 @@
+use quote::quote;
+use syn::{parse_quote, ExprMatch};
+use trie_match_codegen::{
+    trie_match_expansion, trie_match_inner, trie_match_with_strategy, MatchFunction, Strategy,
+};
+
+#[test]
+fn test_generate() {
+    let mut function = MatchFunction::new("/// Doc.\npub fn f(query: &[u8]) -> u32", "query");
+    function
+        .arm("abc", "1")
+        .arm_bytes(b"xyz", "2")
+        .arm("bc", "1")
+        .wildcard("0");
+
+    let expr = trie_match_inner(parse_quote! {
+        match query {
+            "abc" | "bc" => 1,
+            b"xyz" => 2,
+            _ => 0,
+        }
+    })
+    .unwrap();
+    let expected = quote! {
+        #[doc = " Doc."]
+        pub fn f(query: &[u8]) -> u32 {
+            #expr
+        }
+    };
+
+    assert_eq!(function.generate().unwrap(), expected.to_string());
+}
+
+#[test]
+fn test_generate_const_compatible() {
+    let mut function = MatchFunction::new("pub const fn f(query: &str) -> u32", "query");
+    function.arm("abc", "1").wildcard("0").const_compatible();
+
+    let expr = trie_match_inner(parse_quote! {
+        #[const_compatible]
+        match query {
+            "abc" => 1,
+            _ => 0,
+        }
+    })
+    .unwrap();
+    let expected = quote! {
+        pub const fn f(query: &str) -> u32 {
+            #expr
+        }
+    };
+
+    assert_eq!(function.generate().unwrap(), expected.to_string());
+}
+
+#[test]
+fn test_generate_alphabet_remapping() {
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    function.arm("abc", "1").wildcard("0").alphabet_remapping();
+
+    let expr = trie_match_inner(parse_quote! {
+        #[alphabet_remapping]
+        match query {
+            "abc" => 1,
+            _ => 0,
+        }
+    })
+    .unwrap();
+    let expected = quote! {
+        fn f(query: &str) -> u32 {
+            #expr
+        }
+    };
+
+    assert_eq!(function.generate().unwrap(), expected.to_string());
+}
+
+#[test]
+fn test_generate_minimize() {
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    function
+        .arm("walks", "1")
+        .arm("talks", "1")
+        .wildcard("0")
+        .minimize();
+
+    let expr = trie_match_inner(parse_quote! {
+        #[minimize]
+        match query {
+            "walks" | "talks" => 1,
+            _ => 0,
+        }
+    })
+    .unwrap();
+    let expected = quote! {
+        fn f(query: &str) -> u32 {
+            #expr
+        }
+    };
+
+    assert_eq!(function.generate().unwrap(), expected.to_string());
+}
+
+#[test]
+fn test_generate_strategy() {
+    let mut function = MatchFunction::new("fn f(query: &[u8]) -> u32", "query");
+    function
+        .arm("a", "1")
+        .arm_bytes(b"bc", "2")
+        .wildcard("0")
+        .strategy(Strategy::Length);
+
+    let expr = trie_match_inner(parse_quote! {
+        #[strategy(length)]
+        match query {
+            "a" => 1,
+            b"bc" => 2,
+            _ => 0,
+        }
+    })
+    .unwrap();
+    let expected = quote! {
+        fn f(query: &[u8]) -> u32 {
+            #expr
+        }
+    };
+
+    assert_eq!(function.generate().unwrap(), expected.to_string());
+
+    function.strategy(Strategy::DecisionTree);
+    let code = function.generate().unwrap();
+    assert!(code.contains("match query_ref [0]"));
+    assert!(!code.contains("__TrieMatchSlot"));
+}
+
+#[test]
+fn test_generate_perfect_hash() {
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    for i in 0..10000 {
+        function.arm(format!("pattern{i}"), (i % 7).to_string());
+    }
+    function.wildcard("7").strategy(Strategy::PerfectHash);
+    let code = function.generate().unwrap();
+    assert!(code.contains("DISPLACEMENTS"));
+    assert!(!code.contains("__TrieMatchSlot"));
+    assert_eq!(code.matches("b\"pattern").count(), 10000);
+}
+
+#[test]
+fn test_select_strategy() {
+    let (_, strategy) = trie_match_with_strategy(parse_quote! {
+        match query {
+            "a" | "abbr" => 0,
+            "div" | "span" => 1,
+            _ => 2,
+        }
+    })
+    .unwrap();
+    assert_ne!(strategy, Strategy::DoubleArray);
+
+    // Nested prefixes have distinct lengths, but the decision tree has no branches to split
+    // them.
+    let (_, strategy) = trie_match_with_strategy(parse_quote! {
+        match query {
+            "in" => 0,
+            "int" => 1,
+            "inter" => 2,
+            "internal" => 3,
+            "international" => 4,
+            _ => 5,
+        }
+    })
+    .unwrap();
+    assert_eq!(strategy, Strategy::Length);
+
+    // Long patterns need many transitions of the double-array, while the hash of a pattern needs
+    // only a few multiplications.
+    let numbers = (0..2000).map(|i| format!("{i:016}"));
+    let (_, strategy) = trie_match_with_strategy(parse_quote! {
+        match query {
+            #( #numbers )|* => 0,
+            _ => 1,
+        }
+    })
+    .unwrap();
+    assert_eq!(strategy, Strategy::PerfectHash);
+
+    // The code without tables does not fit in the cache.
+    let numbers = (0..2000).map(|i| i.to_string());
+    let (_, strategy) = trie_match_with_strategy(parse_quote! {
+        match query {
+            #( #numbers )|* => 0,
+            _ => 1,
+        }
+    })
+    .unwrap();
+    assert_eq!(strategy, Strategy::DoubleArray);
+
+    // Options of the double-array select it.
+    let (_, strategy) = trie_match_with_strategy(parse_quote! {
+        #[alphabet_remapping]
+        match query {
+            "a" => 0,
+            _ => 1,
+        }
+    })
+    .unwrap();
+    assert_eq!(strategy, Strategy::DoubleArray);
+
+    // `match` is a keyword.
+    let (_, strategy) = trie_match_with_strategy(parse_quote! {
+        #[strategy(match)]
+        match query {
+            "a" => 0,
+            _ => 1,
+        }
+    })
+    .unwrap();
+    assert_eq!(strategy, Strategy::Match);
+}
+
+#[test]
+fn test_report_strategy() {
+    let report = |input: ExprMatch| {
+        let code = trie_match_inner(input).unwrap().to_string();
+        let start = code.find("uses the `").unwrap() + "uses the `".len();
+        let len = code[start..].find('`').unwrap();
+        code[start..start + len].to_string()
+    };
+
+    assert_eq!(
+        report(parse_quote! {
+            #[report_strategy]
+            match query {
+                "abc" => 0,
+                _ => 1,
+            }
+        }),
+        "match",
+    );
+    assert_eq!(
+        report(parse_quote! {
+            #[report_strategy]
+            match query {
+                "a" | "abbr" => 0,
+                "div" | "span" => 1,
+                _ => 2,
+            }
+        }),
+        "decision_tree",
+    );
+    assert_eq!(
+        report(parse_quote! {
+            #[report_strategy]
+            match query {
+                "in" => 0,
+                "int" => 1,
+                "inter" => 2,
+                "internal" => 3,
+                "international" => 4,
+                _ => 5,
+            }
+        }),
+        "length",
+    );
+    let numbers = (0..2000).map(|i| i.to_string());
+    assert_eq!(
+        report(parse_quote! {
+            #[report_strategy]
+            match query {
+                #( #numbers )|* => 0,
+                _ => 1,
+            }
+        }),
+        "double_array",
+    );
+    let numbers = (0..2000).map(|i| format!("{i:016}"));
+    assert_eq!(
+        report(parse_quote! {
+            #[report_strategy]
+            match query {
+                #( #numbers )|* => 0,
+                _ => 1,
+            }
+        }),
+        "perfect_hash",
+    );
+
+    // The layout of the double-array is also reported.
+    let code = trie_match_inner(parse_quote! {
+        #[report_strategy]
+        #[strategy(double_array)]
+        match query {
+            "a" => 0,
+            _ => 1,
+        }
+    })
+    .unwrap()
+    .to_string();
+    assert!(code.contains("uses the `double_array` strategy with 2 states in 2 slots"));
+
+    let code = trie_match_inner(parse_quote! {
+        match query {
+            "a" => 0,
+            _ => 1,
+        }
+    })
+    .unwrap()
+    .to_string();
+    assert!(!code.contains("__trie_match_report_strategy"));
+
+    let err = trie_match_inner(parse_quote! {
+        #[report_strategy]
+        #[report_strategy]
+        match query {
+            "a" => 0,
+            _ => 1,
+        }
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), "duplicate `report_strategy` attribute");
+}
+
+#[test]
+fn test_strategy_errors() {
+    let err = trie_match_inner(parse_quote! {
+        #[strategy(linear)]
+        match query {
+            "a" => 1,
+            _ => 0,
+        }
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown strategy: expected `auto`, `double_array`, `length`, `decision_tree`, `match`, or `perfect_hash`",
+    );
+
+    let err = trie_match_inner(parse_quote! {
+        #[strategy(length)]
+        #[strategy(double_array)]
+        match query {
+            "a" => 1,
+            _ => 0,
+        }
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), "duplicate `strategy` attribute");
+}
+
+#[test]
+fn test_generate_suggestion_rows() {
+    let expr = trie_match_inner(parse_quote! {
+        match query {
+            "a" => 1,
+            "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz" => 2,
+            #[suggest(candidates, distance = 2)]
+            _ => candidates.count(),
+        }
+    })
+    .unwrap();
+    // A row holds the band of 5 cells for each of the 53 depths, regardless of the query length.
+    assert!(expr
+        .to_string()
+        .contains("rows : [[u8 ; 5usize] ; 53usize]"));
+}
+
+#[test]
+fn test_generate_narrow_bases() {
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    function
+        .arm("abc", "1")
+        .arm("abd", "2")
+        .wildcard("0")
+        .strategy(Strategy::DoubleArray);
+    assert!(function
+        .generate()
+        .unwrap()
+        .contains("struct __TrieMatchSlot (i8 , u8 , __TrieMatchValue)"));
+
+    let mut function = MatchFunction::new("fn f(query: &str) -> usize", "query");
+    for i in 0..1000 {
+        function.arm(format!("{i:03}"), i.to_string());
+    }
+    // Padded slots would have unsigned base values.
+    function
+        .wildcard("usize::MAX")
+        .strategy(Strategy::DoubleArray)
+        .const_compatible();
+    assert!(function
+        .generate()
+        .unwrap()
+        .contains("struct __TrieMatchSlot (i16 , u8 , __TrieMatchValue)"));
+}
+
+#[test]
+fn test_generate_bounds() {
+    // Tails make the padding too large, so positions are clamped to a sentinel slot.
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    function
+        .arm("abc", "1")
+        .arm("abd", "2")
+        .wildcard("0")
+        .strategy(Strategy::DoubleArray);
+    let code = function.generate().unwrap();
+    assert!(code.contains(". min ("));
+    assert!(!code.contains("slots . get (pos)"));
+
+    let mut function = MatchFunction::new("fn f(query: &str) -> usize", "query");
+    for i in 0..1000 {
+        function.arm(format!("{i:03}"), i.to_string());
+    }
+    function
+        .wildcard("usize::MAX")
+        .strategy(Strategy::DoubleArray);
+    let code = function.generate().unwrap();
+    assert!(code.contains("struct __TrieMatchSlot (u16 , u8 , __TrieMatchValue)"));
+    assert!(code.contains("base as usize + usize :: from (c)"));
+    assert!(!code.contains(". min ("));
+    assert!(!code.contains("slots . get (pos)"));
+}
+
+#[test]
+fn test_generate_word_comparison() {
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    function
+        .arm("application/json", "1")
+        .arm("application/xml", "2")
+        .wildcard("0")
+        .strategy(Strategy::DoubleArray);
+    let code = function.generate().unwrap();
+    assert!(code.contains("read_unaligned"));
+    assert!(code.contains("u64 :: from_ne_bytes (* b\"applicat\")"));
+    assert!(code.contains("u64 :: from_ne_bytes (* b\"ication/\")"));
+
+    function.const_compatible();
+    let code = function.generate().unwrap();
+    assert!(!code.contains("read_unaligned"));
+    assert!(code.contains("u64 :: from_ne_bytes (* b\"applicat\")"));
+}
+
+#[test]
+fn test_expansion_layout() {
+    let expansion = trie_match_expansion(parse_quote! {
+        #[strategy(double_array)]
+        match query {
+            "a" | "abbr" | "address" | "area" | "article" => 1,
+            "b" | "base" | "bdi" | "bdo" | "blockquote" | "body" => 2,
+            _ => 0,
+        }
+    })
+    .unwrap();
+    let layout = expansion.layout.unwrap();
+    assert_eq!(expansion.strategy, Strategy::DoubleArray);
+    assert!(layout.num_states <= layout.num_slots);
+    assert_eq!(
+        layout.fill_ratio(),
+        layout.num_states as f64 / layout.num_slots as f64
+    );
+
+    let expansion = trie_match_expansion(parse_quote! {
+        #[strategy(length)]
+        match query {
+            "a" => 1,
+            _ => 0,
+        }
+    })
+    .unwrap();
+    assert_eq!(expansion.layout, None);
+}
+
+#[test]
+fn test_generate_deterministic() {
+    let mut function = MatchFunction::new("fn f(query: &str) -> usize", "query");
+    for i in 0..100 {
+        function.arm(format!("pattern{i}"), i.to_string());
+    }
+    function.wildcard("usize::MAX");
+
+    assert_eq!(function.generate().unwrap(), function.generate().unwrap());
+}
+
+#[test]
+fn test_generate_errors() {
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    function.arm("a", "1").arm("a", "2").wildcard("0");
+    assert_eq!(
+        function.generate().unwrap_err().to_string(),
+        "duplicate pattern: \"a\"",
+    );
+
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    function.arm("a", "1");
+    assert_eq!(
+        function.generate().unwrap_err().to_string(),
+        "non-exhaustive patterns: `_` not covered",
+    );
+
+    let mut function = MatchFunction::new("fn f(query: &str) -> u32", "query");
+    function.arm("a", "1 +").wildcard("0");
+    assert!(function.generate().is_err());
+}
+
+#[test]
+fn test_perfect_hash_not_found() {
+    // Flipping the top bit of the first word passes the multiplication as the top bit, which the
+    // rotation moves to bit 28, and flipping it in the second word cancels it. Thus, the patterns
+    // have the same hash for every seed.
+    let mut colliding = *b"zzzzzzzzzzzzzzzz";
+    let colliding_pair = [colliding, {
+        colliding[7] ^= 0x80;
+        colliding[11] ^= 0x10;
+        colliding
+    }];
+    let function = |strategy| {
+        let mut function = MatchFunction::new("fn f(query: &[u8]) -> u32", "query");
+        for i in 0..2000 {
+            function.arm_bytes(format!("{i:016}"), "1");
+        }
+        for pattern in colliding_pair {
+            function.arm_bytes(pattern, "2");
+        }
+        function.wildcard("0").strategy(strategy);
+        function
+    };
+
+    // The cost model selects the perfect hash, which falls back to the double-array.
+    let code = function(Strategy::Auto).generate().unwrap();
+    assert!(!code.contains("DISPLACEMENTS"));
+    assert!(code.contains("__TrieMatchSlot"));
+
+    assert_eq!(
+        function(Strategy::PerfectHash)
+            .generate()
+            .unwrap_err()
+            .to_string(),
+        "failed to find a perfect hash function of the patterns",
+    );
+}
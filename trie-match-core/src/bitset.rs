@@ -0,0 +1,47 @@
+use alloc::vec::Vec;
+
+/// Growable set of non-negative integers stored as bits.
+///
+/// [`next_absent`](Self::next_absent) skips 64 present integers at a time, so searching for a
+/// free position in a densely used double-array does not visit each used position.
+#[derive(Clone, Debug, Default)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// Creates an empty set.
+    pub const fn new() -> Self {
+        Self { words: Vec::new() }
+    }
+
+    /// Returns `true` if the set contains `i`.
+    pub fn contains(&self, i: usize) -> bool {
+        self.words
+            .get(i / 64)
+            .is_some_and(|&word| word & (1 << (i % 64)) != 0)
+    }
+
+    /// Adds `i` to the set.
+    pub fn insert(&mut self, i: usize) {
+        if i / 64 >= self.words.len() {
+            self.words.resize(i / 64 + 1, 0);
+        }
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    /// Returns the smallest integer not less than `i` that is not in the set.
+    pub fn next_absent(&self, i: usize) -> usize {
+        let mut idx = i / 64;
+        let Some(&word) = self.words.get(idx) else {
+            return i;
+        };
+        // Treats the integers before `i` in the first word as present.
+        let mut word = word | ((1 << (i % 64)) - 1);
+        while word == u64::MAX {
+            idx += 1;
+            word = self.words.get(idx).copied().unwrap_or(0);
+        }
+        idx * 64 + (!word).trailing_zeros() as usize
+    }
+}
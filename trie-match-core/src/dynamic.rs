@@ -0,0 +1,318 @@
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Double-array that supports insertion and removal of patterns at run time.
+///
+/// This uses the same layout as [`DoubleArray`](crate::DoubleArray). A transition from the state
+/// at position `pos` with a byte `b` moves to position `bases[pos] + b` if
+/// `checks[bases[pos] + b] == b`, the root is at position 0, base values are unique, and 0 is
+/// never used as a base value. States without children have `i32::MAX` as the base value.
+///
+/// When a new edge conflicts with another state, the children of the source state are relocated
+/// to a new base value.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match_core::DynamicDoubleArray;
+///
+/// let mut da = DynamicDoubleArray::new();
+/// da.insert("a", 1);
+/// da.insert("abc", 2);
+/// da.insert("bc", 3);
+///
+/// assert_eq!(da.get("abc"), Some(&2));
+/// assert_eq!(da.get("ab"), None);
+///
+/// assert_eq!(da.remove("abc"), Some(2));
+/// assert_eq!(da.get("abc"), None);
+///
+/// da.insert("ab", 4);
+/// let matches: Vec<_> = da.common_prefix_search("abd").collect();
+/// assert_eq!(matches, [(1, &1), (2, &4)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DynamicDoubleArray<T> {
+    bases: Vec<i32>,
+    checks: Vec<u8>,
+    values: Vec<Option<T>>,
+    is_used: Vec<bool>,
+    used_bases: BTreeSet<i32>,
+    len: usize,
+
+    // No positions before this are unused.
+    search_start: usize,
+}
+
+impl<T> Default for DynamicDoubleArray<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P> FromIterator<(P, T)> for DynamicDoubleArray<T>
+where
+    P: AsRef<[u8]>,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut da = Self::new();
+        da.extend(iter);
+        da
+    }
+}
+
+impl<T, P> Extend<(P, T)> for DynamicDoubleArray<T>
+where
+    P: AsRef<[u8]>,
+{
+    fn extend<I: IntoIterator<Item = (P, T)>>(&mut self, iter: I) {
+        for (pattern, value) in iter {
+            self.insert(pattern, value);
+        }
+    }
+}
+
+impl<T> DynamicDoubleArray<T> {
+    /// Creates an empty double-array.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            bases: vec![i32::MAX],
+            checks: vec![0],
+            values: vec![None],
+            is_used: vec![true],
+            // base=0 must be reserved for avoiding invalid transitions.
+            // See https://github.com/daac-tools/trie-match/pull/11.
+            used_bases: BTreeSet::from([0]),
+            len: 0,
+            search_start: 1,
+        }
+    }
+
+    /// Returns the number of patterns.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no pattern is stored.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the base values. Unused positions and positions without children have
+    /// `i32::MAX`.
+    #[must_use]
+    pub fn bases(&self) -> &[i32] {
+        &self.bases
+    }
+
+    /// Returns the check values, which are labels of incoming edges. Unused positions have 0.
+    #[must_use]
+    pub fn checks(&self) -> &[u8] {
+        &self.checks
+    }
+
+    /// Returns the values of positions. Positions without patterns have `None`.
+    #[must_use]
+    pub fn values(&self) -> &[Option<T>] {
+        &self.values
+    }
+
+    /// Returns the position reached from `pos` with the label `b`.
+    fn child(&self, pos: usize, b: u8) -> Option<usize> {
+        let next = usize::try_from(self.bases[pos].wrapping_add(i32::from(b))).ok()?;
+        (self.is_used.get(next) == Some(&true) && self.checks[next] == b).then_some(next)
+    }
+
+    /// Returns the labels of children of `pos` in ascending order.
+    fn children(&self, pos: usize) -> Vec<u8> {
+        if self.bases[pos] == i32::MAX {
+            return vec![];
+        }
+        (0..=u8::MAX)
+            .filter(|&b| self.child(pos, b).is_some())
+            .collect()
+    }
+
+    /// Returns the position of the query.
+    fn find(&self, query: &[u8]) -> Option<usize> {
+        let mut pos = 0;
+        for &b in query {
+            pos = self.child(pos, b)?;
+        }
+        Some(pos)
+    }
+
+    fn is_free(&self, pos: i32) -> bool {
+        usize::try_from(pos).is_ok_and(|pos| self.is_used.get(pos) != Some(&true))
+    }
+
+    /// Finds an unused base value whose children with the given labels do not conflict with
+    /// other states.
+    fn find_base(&mut self, labels: &[u8]) -> i32 {
+        while self.is_used.get(self.search_start) == Some(&true) {
+            self.search_start += 1;
+        }
+        let mut pos = self.search_start;
+        loop {
+            if self.is_used.get(pos) != Some(&true) {
+                let base_cand = i32::try_from(pos).unwrap() - i32::from(labels[0]);
+                if !self.used_bases.contains(&base_cand)
+                    && labels
+                        .iter()
+                        .all(|&k| self.is_free(base_cand + i32::from(k)))
+                {
+                    return base_cand;
+                }
+            }
+            pos += 1;
+        }
+    }
+
+    /// Marks the position as used, extending arrays if necessary.
+    fn allocate(&mut self, pos: usize, label: u8) {
+        if pos >= self.bases.len() {
+            self.bases.resize(pos + 1, i32::MAX);
+            self.checks.resize(pos + 1, 0);
+            self.values.resize_with(pos + 1, || None);
+            self.is_used.resize(pos + 1, false);
+        }
+        self.checks[pos] = label;
+        self.is_used[pos] = true;
+    }
+
+    fn release(&mut self, pos: usize) {
+        self.bases[pos] = i32::MAX;
+        self.checks[pos] = 0;
+        self.values[pos] = None;
+        self.is_used[pos] = false;
+        self.search_start = self.search_start.min(pos);
+    }
+
+    /// Moves the children of `pos` so that its base value becomes `new_base`.
+    fn relocate(&mut self, pos: usize, new_base: i32) {
+        let old_base = self.bases[pos];
+        for k in self.children(pos) {
+            let old_pos = usize::try_from(old_base + i32::from(k)).unwrap();
+            let new_pos = usize::try_from(new_base + i32::from(k)).unwrap();
+            self.allocate(new_pos, k);
+            self.bases[new_pos] = self.bases[old_pos];
+            self.values[new_pos] = self.values[old_pos].take();
+            self.release(old_pos);
+        }
+        if old_base != i32::MAX {
+            self.used_bases.remove(&old_base);
+        }
+        self.used_bases.insert(new_base);
+        self.bases[pos] = new_base;
+    }
+
+    /// Adds a child with the label `b` to `pos` and returns its position.
+    fn add_child(&mut self, pos: usize, b: u8) -> usize {
+        let base = self.bases[pos];
+        if base == i32::MAX || !self.is_free(base + i32::from(b)) {
+            let mut labels = self.children(pos);
+            labels.push(b);
+            labels.sort_unstable();
+            let new_base = self.find_base(&labels);
+            self.relocate(pos, new_base);
+        }
+        let child = usize::try_from(self.bases[pos] + i32::from(b)).unwrap();
+        self.allocate(child, b);
+        child
+    }
+
+    /// Returns the value of the pattern.
+    pub fn get(&self, pattern: impl AsRef<[u8]>) -> Option<&T> {
+        self.values[self.find(pattern.as_ref())?].as_ref()
+    }
+
+    /// Returns the mutable reference to the value of the pattern.
+    pub fn get_mut(&mut self, pattern: impl AsRef<[u8]>) -> Option<&mut T> {
+        let pos = self.find(pattern.as_ref())?;
+        self.values[pos].as_mut()
+    }
+
+    /// Inserts a pattern and returns the old value if the pattern is already stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the double-array exceeds `i32::MAX`.
+    pub fn insert(&mut self, pattern: impl AsRef<[u8]>, value: T) -> Option<T> {
+        let mut pos = 0;
+        for &b in pattern.as_ref() {
+            pos = self.child(pos, b).unwrap_or_else(|| self.add_child(pos, b));
+        }
+        let old = self.values[pos].replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Removes a pattern and returns its value. States that are no longer needed are released.
+    pub fn remove(&mut self, pattern: impl AsRef<[u8]>) -> Option<T> {
+        let mut pos = 0;
+        let mut path = vec![pos];
+        for &b in pattern.as_ref() {
+            pos = self.child(pos, b)?;
+            path.push(pos);
+        }
+        let value = self.values[pos].take()?;
+        self.len -= 1;
+        // Releases states without patterns or children from the leaf.
+        while let [.., parent, child] = path[..] {
+            if self.values[child].is_some() || self.bases[child] != i32::MAX {
+                break;
+            }
+            self.release(child);
+            if self.children(parent).is_empty() {
+                self.used_bases.remove(&self.bases[parent]);
+                self.bases[parent] = i32::MAX;
+            }
+            path.pop();
+        }
+        Some(value)
+    }
+
+    /// Returns an iterator over patterns that are prefixes of the query. Each item is a tuple of
+    /// the length of the pattern and its value, in ascending order of the length.
+    pub fn common_prefix_search<'a, Q>(&'a self, query: &'a Q) -> CommonPrefixSearch<'a, T>
+    where
+        Q: AsRef<[u8]> + ?Sized,
+    {
+        CommonPrefixSearch {
+            da: self,
+            query: query.as_ref(),
+            pos: Some(0),
+            len: 0,
+        }
+    }
+}
+
+/// Iterator created by [`DynamicDoubleArray::common_prefix_search`].
+pub struct CommonPrefixSearch<'a, T> {
+    da: &'a DynamicDoubleArray<T>,
+    query: &'a [u8],
+    pos: Option<usize>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for CommonPrefixSearch<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let pos = self.pos?;
+            let len = self.len;
+            self.pos = self.query.get(len).and_then(|&b| self.da.child(pos, b));
+            self.len += 1;
+            if let Some(value) = &self.da.values[pos] {
+                return Some((len, value));
+            }
+        }
+    }
+}
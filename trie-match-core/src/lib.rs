@@ -0,0 +1,56 @@
+//! # trie-match-core
+//!
+//! The builder of compact double-arrays used by the [`trie-match`](https://docs.rs/trie-match)
+//! macros.
+//!
+//! The macros embed [`DoubleArray`] into the generated code. This crate makes the same
+//! representation available at run time and in `build.rs`.
+//!
+//! ```
+//! use trie_match_core::Sparse;
+//!
+//! let trie: Sparse<u32> = [("a", 1), ("abc", 2), ("bc", 3)].into_iter().collect();
+//! let da = trie.build_double_array_trie(0);
+//!
+//! assert_eq!(*da.get(b"a"), 1);
+//! assert_eq!(*da.get(b"abc"), 2);
+//! assert_eq!(*da.get(b"bc"), 3);
+//! assert_eq!(*da.get(b"ab"), 0);
+//! ```
+//!
+//! [`DynamicDoubleArray`] uses the same representation and supports insertion and removal of
+//! patterns at run time.
+//!
+//! [`DoubleArray::serialize`] converts a double-array into bytes, and [`DoubleArrayRef`] queries
+//! them without copying. [`DoubleArrayRef`] is available without the `alloc` feature.
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+mod bitset;
+#[cfg(feature = "alloc")]
+mod dynamic;
+mod serialize;
+#[cfg(feature = "alloc")]
+mod trie;
+
+#[cfg(feature = "alloc")]
+pub use dynamic::{CommonPrefixSearch, DynamicDoubleArray};
+pub use serialize::{DeserializeError, DoubleArrayRef, Endianness};
+#[cfg(feature = "alloc")]
+pub use trie::{
+    BuildOptions, DoubleArray, LayoutStats, MinimizationStats, Placement, Segment, Sparse,
+};
+
+/// Base value of the state whose suffix is the first tail. The base value of the state with the
+/// `i`-th tail is `TAIL_BASE + i`.
+pub const TAIL_BASE: i32 = i32::MIN;
+
+/// Returns the index of the tail if the base value indicates a tail.
+fn tail_index(base: i32, num_tails: usize) -> Option<usize> {
+    let idx = usize::try_from(i64::from(base) - i64::from(TAIL_BASE)).ok()?;
+    (idx < num_tails).then_some(idx)
+}
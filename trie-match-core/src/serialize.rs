@@ -0,0 +1,459 @@
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::DoubleArray;
+use crate::{tail_index, TAIL_BASE};
+
+const MAGIC: &[u8; 4] = b"TMDA";
+const VERSION: u8 = 3;
+const HEADER_SIZE: usize = 20;
+
+/// Flag indicating that the codes of bytes are stored.
+const FLAG_CODES: u8 = 1;
+
+/// Flag indicating that segments are stored.
+const FLAG_SEGMENTS: u8 = 2;
+
+/// Size of an entry of tails or segments.
+const ENTRY_SIZE: usize = 12;
+
+/// Byte order of a serialized double-array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// Little endian.
+    Little,
+
+    /// Big endian.
+    Big,
+}
+
+impl Endianness {
+    /// Byte order of the current target.
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Self = Self::Little;
+
+    /// Byte order of the current target.
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Self = Self::Big;
+
+    #[cfg(feature = "alloc")]
+    const fn tag(self) -> u8 {
+        match self {
+            Self::Little => b'L',
+            Self::Big => b'B',
+        }
+    }
+
+    const fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            b'L' => Some(Self::Little),
+            b'B' => Some(Self::Big),
+            _ => None,
+        }
+    }
+
+    fn read_u32(self, bytes: &[u8]) -> u32 {
+        let bytes = bytes.try_into().unwrap();
+        match self {
+            Self::Little => u32::from_le_bytes(bytes),
+            Self::Big => u32::from_be_bytes(bytes),
+        }
+    }
+
+    fn read_i32(self, bytes: &[u8]) -> i32 {
+        let bytes = bytes.try_into().unwrap();
+        match self {
+            Self::Little => i32::from_le_bytes(bytes),
+            Self::Big => i32::from_be_bytes(bytes),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    const fn u32_bytes(self, x: u32) -> [u8; 4] {
+        match self {
+            Self::Little => x.to_le_bytes(),
+            Self::Big => x.to_be_bytes(),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    const fn i32_bytes(self, x: i32) -> [u8; 4] {
+        match self {
+            Self::Little => x.to_le_bytes(),
+            Self::Big => x.to_be_bytes(),
+        }
+    }
+}
+
+/// Error returned by [`DoubleArrayRef::from_bytes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeserializeError {
+    /// The data does not start with the magic number.
+    InvalidMagic,
+
+    /// The format version is not supported.
+    UnsupportedVersion(u8),
+
+    /// The endianness tag is invalid.
+    InvalidEndianness(u8),
+
+    /// The flags contain unknown bits.
+    UnsupportedFlags(u8),
+
+    /// The size of the data does not match the header.
+    InvalidLength,
+
+    /// The checksum does not match the data.
+    ChecksumMismatch,
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "invalid magic number"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported format version: {v}"),
+            Self::InvalidEndianness(tag) => write!(f, "invalid endianness tag: {tag:#04x}"),
+            Self::UnsupportedFlags(flags) => write!(f, "unsupported flags: {flags:#04x}"),
+            Self::InvalidLength => write!(f, "data length does not match the header"),
+            Self::ChecksumMismatch => write!(f, "checksum mismatch"),
+        }
+    }
+}
+
+/// 32-bit FNV-1a hash.
+fn checksum<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> u32 {
+    let mut hash = 0x811c_9dc5_u32;
+    for chunk in chunks {
+        for &b in chunk {
+            hash ^= u32::from(b);
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+    }
+    hash
+}
+
+#[cfg(feature = "alloc")]
+impl<T> DoubleArray<T>
+where
+    T: Copy + Into<u32>,
+{
+    /// Serializes the double-array into bytes that can be loaded by
+    /// [`DoubleArrayRef::from_bytes`].
+    ///
+    /// The format consists of the following fields. Integers are stored in the given byte order.
+    ///
+    /// | Offset | Size | Field |
+    /// |---|---|---|
+    /// | 0 | 4 | Magic number `TMDA` |
+    /// | 4 | 1 | Format version (3) |
+    /// | 5 | 1 | Endianness tag (`L` or `B`) |
+    /// | 6 | 1 | Flags (1 if the alphabet is remapped, plus 2 if there are segments) |
+    /// | 7 | 1 | Reserved (0) |
+    /// | 8 | 4 | Length `n` of the arrays as `u32` |
+    /// | 12 | 4 | Wildcard value as `u32` |
+    /// | 16 | 4 | 32-bit FNV-1a hash of the other bytes as `u32` |
+    /// | 20 | 4`n` | Base values as `i32` |
+    /// | 20 + 4`n` | 4`n` | Values as `u32` |
+    /// | 20 + 8`n` | `n` | Check values |
+    /// | 20 + 9`n` | 4 | Number `t` of tails as `u32` |
+    /// | 24 + 9`n` | 12`t` | Offset, length, and value of each tail as `u32` |
+    /// | 24 + 9`n` + 12`t` | | Bytes of tails |
+    /// | | 4 | Number `s` of segments as `u32` if there are segments |
+    /// | | 12`s` | Offset, length, and target position of each segment as `u32` |
+    /// | | | Bytes of segments |
+    /// | | 256 | Codes of bytes if the alphabet is remapped |
+    ///
+    /// Use the byte order of the target instead of [`Endianness::NATIVE`] when serializing in a
+    /// build script for cross compilation. It is available from the `CARGO_CFG_TARGET_ENDIAN`
+    /// environment variable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the double-array or the total length of tails or segments exceeds
+    /// `u32::MAX`.
+    #[must_use]
+    pub fn serialize(&self, endianness: Endianness) -> Vec<u8> {
+        let len = self.bases.len();
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + len * 9);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(endianness.tag());
+        let mut flags = 0;
+        if self.codes.is_some() {
+            flags |= FLAG_CODES;
+        }
+        if !self.segments.is_empty() {
+            flags |= FLAG_SEGMENTS;
+        }
+        bytes.push(flags);
+        bytes.push(0);
+        bytes.extend_from_slice(&endianness.u32_bytes(u32::try_from(len).unwrap()));
+        bytes.extend_from_slice(&endianness.u32_bytes(self.wildcard.into()));
+        bytes.extend_from_slice(&[0; 4]);
+        for &base in &self.bases {
+            bytes.extend_from_slice(&endianness.i32_bytes(base));
+        }
+        for &value in &self.values {
+            bytes.extend_from_slice(&endianness.u32_bytes(value.into()));
+        }
+        bytes.extend_from_slice(&self.checks);
+        let tails = self
+            .tails
+            .iter()
+            .map(|(tail, value)| (&tail[..], (*value).into()));
+        write_entries(&mut bytes, tails, endianness);
+        if !self.segments.is_empty() {
+            let segments = self.segments.iter().map(|segment| {
+                let target = u32::try_from(segment.target).unwrap();
+                (&segment.bytes[..], target)
+            });
+            write_entries(&mut bytes, segments, endianness);
+        }
+        if let Some(codes) = &self.codes {
+            bytes.extend_from_slice(codes);
+        }
+        let hash = checksum([&bytes[..16], &bytes[HEADER_SIZE..]]);
+        bytes[16..HEADER_SIZE].copy_from_slice(&endianness.u32_bytes(hash));
+        bytes
+    }
+}
+
+/// Writes the number of entries, the offset, the length, and the value of each entry, and the
+/// bytes of the entries.
+#[cfg(feature = "alloc")]
+fn write_entries<'a>(
+    bytes: &mut Vec<u8>,
+    entries: impl Iterator<Item = (&'a [u8], u32)> + Clone,
+    endianness: Endianness,
+) {
+    let num_entries = u32::try_from(entries.clone().count()).unwrap();
+    bytes.extend_from_slice(&endianness.u32_bytes(num_entries));
+    let mut offset = 0_u32;
+    for (entry, value) in entries.clone() {
+        let len = u32::try_from(entry.len()).unwrap();
+        bytes.extend_from_slice(&endianness.u32_bytes(offset));
+        bytes.extend_from_slice(&endianness.u32_bytes(len));
+        bytes.extend_from_slice(&endianness.u32_bytes(value));
+        offset = offset.checked_add(len).unwrap();
+    }
+    for (entry, _) in entries {
+        bytes.extend_from_slice(entry);
+    }
+}
+
+/// Reads the number of entries at `start` of `arrays`, and returns the entries, the bytes of the
+/// entries, and the end of the bytes.
+fn read_entries(
+    arrays: &[u8],
+    start: usize,
+    endianness: Endianness,
+) -> Result<(&[u8], &[u8], usize), DeserializeError> {
+    // The sizes are checked without overflow since `usize` may be 32 bits.
+    let entries_start = start
+        .checked_add(4)
+        .filter(|&end| end <= arrays.len())
+        .ok_or(DeserializeError::InvalidLength)?;
+    let num_entries = usize::try_from(endianness.read_u32(&arrays[start..entries_start]))
+        .map_err(|_| DeserializeError::InvalidLength)?;
+    let bytes_start = num_entries
+        .checked_mul(ENTRY_SIZE)
+        .and_then(|size| size.checked_add(entries_start))
+        .filter(|&end| end <= arrays.len())
+        .ok_or(DeserializeError::InvalidLength)?;
+    let entries = &arrays[entries_start..bytes_start];
+    let bytes_len: u64 = entries
+        .chunks_exact(ENTRY_SIZE)
+        .map(|entry| u64::from(endianness.read_u32(&entry[4..8])))
+        .sum();
+    let bytes_end = usize::try_from(bytes_len)
+        .ok()
+        .and_then(|len| len.checked_add(bytes_start))
+        .filter(|&end| end <= arrays.len())
+        .ok_or(DeserializeError::InvalidLength)?;
+    Ok((entries, &arrays[bytes_start..bytes_end], bytes_end))
+}
+
+/// Double-array borrowed from serialized bytes.
+///
+/// Loading neither copies nor allocates, and the bytes need not be aligned, so the data can be
+/// embedded with `include_bytes!` or memory-mapped. Queries traverse the double-array in the same
+/// way as the generated code.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match_core::{DoubleArrayRef, Endianness, Sparse};
+///
+/// let trie: Sparse<u32> = [("a", 1), ("abc", 2), ("bc", 3)].into_iter().collect();
+/// let bytes = trie.build_double_array_trie(0).serialize(Endianness::NATIVE);
+///
+/// let da = DoubleArrayRef::from_bytes(&bytes).unwrap();
+/// assert_eq!(da.get(b"abc"), 2);
+/// assert_eq!(da.get(b"ab"), 0);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct DoubleArrayRef<'a> {
+    bases: &'a [u8],
+    values: &'a [u8],
+    checks: &'a [u8],
+    tails: &'a [u8],
+    tail_bytes: &'a [u8],
+    segments: &'a [u8],
+    segment_bytes: &'a [u8],
+    codes: Option<&'a [u8]>,
+    wildcard: u32,
+    endianness: Endianness,
+}
+
+impl<'a> DoubleArrayRef<'a> {
+    /// Loads a double-array serialized by [`DoubleArray::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is invalid or if the checksum does not match.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, DeserializeError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(DeserializeError::InvalidLength);
+        }
+        let (header, body) = bytes.split_at(HEADER_SIZE);
+        if &header[..4] != MAGIC {
+            return Err(DeserializeError::InvalidMagic);
+        }
+        if header[4] != VERSION {
+            return Err(DeserializeError::UnsupportedVersion(header[4]));
+        }
+        let endianness = Endianness::from_tag(header[5])
+            .ok_or(DeserializeError::InvalidEndianness(header[5]))?;
+        let flags = header[6];
+        if flags & !(FLAG_CODES | FLAG_SEGMENTS) != 0 {
+            return Err(DeserializeError::UnsupportedFlags(flags));
+        }
+        let codes_len = if flags & FLAG_CODES == 0 { 0 } else { 256 };
+        let arrays_len = body
+            .len()
+            .checked_sub(codes_len)
+            .ok_or(DeserializeError::InvalidLength)?;
+        let (arrays, codes) = body.split_at(arrays_len);
+        let len = usize::try_from(endianness.read_u32(&header[8..12]))
+            .map_err(|_| DeserializeError::InvalidLength)?;
+        let tails_start = len
+            .checked_mul(9)
+            .filter(|_| len != 0)
+            .ok_or(DeserializeError::InvalidLength)?;
+        let (tails, tail_bytes, tails_end) = read_entries(arrays, tails_start, endianness)?;
+        let (segments, segment_bytes, end) = if flags & FLAG_SEGMENTS == 0 {
+            (&[][..], &[][..], tails_end)
+        } else {
+            read_entries(arrays, tails_end, endianness)?
+        };
+        if end != arrays.len() {
+            return Err(DeserializeError::InvalidLength);
+        }
+        if endianness.read_u32(&header[16..20]) != checksum([&header[..16], body]) {
+            return Err(DeserializeError::ChecksumMismatch);
+        }
+        let (bases, rest) = arrays.split_at(len * 4);
+        let (values, checks) = rest.split_at(len * 4);
+        Ok(Self {
+            bases,
+            values,
+            checks: &checks[..len],
+            tails,
+            tail_bytes,
+            segments,
+            segment_bytes,
+            codes: (codes_len != 0).then_some(codes),
+            wildcard: endianness.read_u32(&header[12..16]),
+            endianness,
+        })
+    }
+
+    /// Returns the number of positions.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.checks.len()
+    }
+
+    /// Returns `true` if the double-array has no positions. This is always `false` for a loaded
+    /// double-array because it has the root.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    fn base(&self, pos: usize) -> i32 {
+        self.endianness.read_i32(&self.bases[pos * 4..pos * 4 + 4])
+    }
+
+    fn value(&self, pos: usize) -> u32 {
+        self.endianness.read_u32(&self.values[pos * 4..pos * 4 + 4])
+    }
+
+    /// Returns the value of the query. If the query matches no pattern, the wildcard value is
+    /// returned.
+    #[must_use]
+    pub fn get(&self, query: &[u8]) -> u32 {
+        let mut pos = 0;
+        let mut i = 0;
+        while let Some(&b) = query.get(i) {
+            let c = self.codes.map_or(b, |codes| codes[usize::from(b)]);
+            let next = usize::try_from(self.base(pos).wrapping_add(i32::from(c)));
+            match next {
+                Ok(next) if self.checks.get(next) == Some(&c) => {
+                    pos = next;
+                    i += 1;
+                }
+                _ => match self.get_segment(pos) {
+                    Some((bytes, target)) if query[i..].starts_with(bytes) => {
+                        pos = target;
+                        i += bytes.len();
+                    }
+                    _ => return self.get_tail(pos, &query[i..]),
+                },
+            }
+        }
+        self.value(pos)
+    }
+
+    /// Returns the bytes and the target position of the segment starting from `pos`.
+    ///
+    /// Segments that are empty or whose targets are out of bounds are ignored, so the traversal
+    /// always advances and stays in bounds.
+    fn get_segment(&self, pos: usize) -> Option<(&[u8], usize)> {
+        let num_tails = self.tails.len() / ENTRY_SIZE;
+        let idx = usize::try_from(i64::from(self.base(pos)) - i64::from(TAIL_BASE)).ok()?;
+        let j = idx.checked_sub(num_tails)?;
+        let entry = self
+            .segments
+            .get(j.checked_mul(ENTRY_SIZE)?..)?
+            .get(..ENTRY_SIZE)?;
+        let offset = self.endianness.read_u32(&entry[..4]) as usize;
+        let len = self.endianness.read_u32(&entry[4..8]) as usize;
+        let target = self.endianness.read_u32(&entry[8..]) as usize;
+        let bytes = offset
+            .checked_add(len)
+            .and_then(|end| self.segment_bytes.get(offset..end))?;
+        (!bytes.is_empty() && target < self.len()).then_some((bytes, target))
+    }
+
+    /// Returns the value of the tail of `pos` if it equals the rest of the query.
+    fn get_tail(&self, pos: usize, rest: &[u8]) -> u32 {
+        let Some(i) = tail_index(self.base(pos), self.tails.len() / ENTRY_SIZE) else {
+            return self.wildcard;
+        };
+        let entry = &self.tails[i * ENTRY_SIZE..(i + 1) * ENTRY_SIZE];
+        let offset = self.endianness.read_u32(&entry[..4]) as usize;
+        let len = self.endianness.read_u32(&entry[4..8]) as usize;
+        let tail = offset
+            .checked_add(len)
+            .and_then(|end| self.tail_bytes.get(offset..end));
+        if tail == Some(rest) {
+            self.endianness.read_u32(&entry[8..])
+        } else {
+            self.wildcard
+        }
+    }
+}
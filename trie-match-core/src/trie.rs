@@ -0,0 +1,901 @@
+use alloc::collections::{BTreeMap, BinaryHeap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::bitset::BitSet;
+use crate::{tail_index, TAIL_BASE};
+
+/// Number of times a free position is rejected as the position of a first child before it is no
+/// longer tried. Without the limit, positions that no base value can reach are tried for every
+/// state, and the construction time grows quadratically.
+const MAX_TRIALS: u8 = u8::MAX;
+
+/// Used positions and base values of a double-array under construction.
+struct Allocator {
+    /// Used positions.
+    is_used: BitSet,
+
+    /// Positions that are used or are no longer tried as the position of a first child.
+    is_closed: BitSet,
+
+    /// Number of times each free position was rejected as the position of a first child.
+    trials: Vec<u8>,
+
+    /// Used base values offset by 255, since base values are greater than `-256`.
+    used_bases: BitSet,
+
+    /// No positions before this are open.
+    search_start: usize,
+}
+
+impl Allocator {
+    fn new() -> Self {
+        let mut allocator = Self {
+            is_used: BitSet::new(),
+            is_closed: BitSet::new(),
+            trials: vec![],
+            used_bases: BitSet::new(),
+            search_start: 0,
+        };
+        allocator.use_position(0);
+        // base=0 must be reserved for avoiding invalid transitions.
+        // See https://github.com/daac-tools/trie-match/pull/11.
+        allocator.used_bases.insert(usize::from(u8::MAX));
+        allocator
+    }
+
+    /// Marks the position as used by a child.
+    fn use_position(&mut self, pos: usize) {
+        self.is_used.insert(pos);
+        self.is_closed.insert(pos);
+    }
+
+    /// Finds and reserves a base value whose children with the given labels, sorted in
+    /// ascending order, do not conflict with other states.
+    ///
+    /// The candidates alternately skip to the next open position of the first child and to the
+    /// next unused base value, so dense ranges of used positions and base values are skipped a
+    /// word of [`BitSet`] at a time.
+    fn find_base(&mut self, labels: &[u8]) -> Option<i32> {
+        let (&first, rest) = labels.split_first()?;
+        // The key of the base value placing the first child at `pos` is `pos + offset`.
+        let offset = usize::from(u8::MAX - first);
+        self.search_start = self.is_closed.next_absent(self.search_start);
+        let mut pos = self.search_start;
+        loop {
+            pos = self.is_closed.next_absent(pos);
+            let key = self.used_bases.next_absent(pos + offset);
+            if key == pos + offset
+                && rest
+                    .iter()
+                    .all(|&k| !self.is_used.contains(pos + usize::from(k - first)))
+            {
+                self.used_bases.insert(key);
+                return Some(i32::try_from(key).unwrap() - i32::from(u8::MAX));
+            }
+            if pos >= self.trials.len() {
+                self.trials.resize(pos + 1, 0);
+            }
+            self.trials[pos] += 1;
+            if self.trials[pos] == MAX_TRIALS {
+                self.is_closed.insert(pos);
+            }
+            pos = if key == pos + offset {
+                pos + 1
+            } else {
+                key - offset
+            };
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State<T> {
+    /// Labels and child IDs sorted by the labels. A sorted vector is smaller and faster to
+    /// traverse than a map for at most 256 edges.
+    edges: Vec<(u8, usize)>,
+    value: Option<T>,
+}
+
+impl<T> Default for State<T> {
+    fn default() -> Self {
+        Self {
+            edges: vec![],
+            value: None,
+        }
+    }
+}
+
+/// Compact double-array.
+///
+/// A transition from the state at position `pos` with a byte `b` moves to position
+/// `bases[pos] + b` if `checks[bases[pos] + b] == b`. Otherwise, the query matches no pattern.
+/// The root is at position 0. Base values are unique, and 0 is never used as a base value, so the
+/// check of the root never accepts a transition.
+///
+/// If the double-array is built with [`build_minimized`](Sparse::build_minimized), equivalent
+/// states share a base value, so their children are stored only once. This is possible because
+/// the check value is the label, not the parent.
+///
+/// If the double-array is built with [`build_tail_compressed`](Sparse::build_tail_compressed),
+/// the single-branch suffix of a state can be stored in `tails` instead of the arrays. The base
+/// value of such a state is [`TAIL_BASE`] plus the index `i` of `tails`, so every
+/// transition from the state fails, and the rest of the query is compared with the suffix
+/// instead.
+///
+/// If the double-array is built with [`BuildOptions::path_compression`], a chain of states in the
+/// middle of patterns can be stored in `segments` instead of the arrays. The base value of the
+/// first state of the chain is [`TAIL_BASE`] plus `tails.len()` plus the index `j` of `segments`.
+/// If the rest of the query starts with the bytes of the segment, the traversal skips them and
+/// continues from the position of the last state of the chain.
+///
+/// If the alphabet is remapped, `b` in the above transition is replaced with `codes[b]`, and the
+/// check values are codes instead of bytes.
+#[derive(Clone, Debug)]
+pub struct DoubleArray<T> {
+    /// Base values. Positions without children have `i32::MAX`.
+    pub bases: Vec<i32>,
+
+    /// Check values, which are labels of incoming edges.
+    pub checks: Vec<u8>,
+
+    /// Values of positions. Positions without values have the wildcard value.
+    pub values: Vec<T>,
+
+    /// Positions of states in the double-array, indexed by state IDs of the sparse trie. States
+    /// stored in `tails` or `segments` and descendants of states merged by minimization have
+    /// `usize::MAX`.
+    pub positions: Vec<usize>,
+
+    /// Single-branch suffixes and their values.
+    pub tails: Vec<(Vec<u8>, T)>,
+
+    /// Chains of states skipped by comparing the bytes at once.
+    pub segments: Vec<Segment>,
+
+    /// Codes of bytes if the alphabet is remapped. Bytes that appear in no transition have 0.
+    pub codes: Option<[u8; 256]>,
+
+    /// Value returned if the query matches no pattern.
+    pub wildcard: T,
+}
+
+impl<T> DoubleArray<T> {
+    /// Returns the value of the query. If the query matches no pattern, the wildcard value is
+    /// returned.
+    ///
+    /// This traverses the double-array in the same way as the generated code.
+    pub fn get(&self, query: &[u8]) -> &T {
+        let mut pos = 0;
+        let mut i = 0;
+        while let Some(&b) = query.get(i) {
+            let c = self.codes.map_or(b, |codes| codes[usize::from(b)]);
+            let next = usize::try_from(self.bases[pos].wrapping_add(i32::from(c)));
+            match next {
+                Ok(next) if self.checks.get(next) == Some(&c) => {
+                    pos = next;
+                    i += 1;
+                }
+                _ => match self.get_segment(pos) {
+                    Some(segment) if query[i..].starts_with(&segment.bytes) => {
+                        pos = segment.target;
+                        i += segment.bytes.len();
+                    }
+                    _ => return self.get_tail(pos, &query[i..]),
+                },
+            }
+        }
+        &self.values[pos]
+    }
+
+    /// Returns the number of slots and states of the arrays.
+    #[must_use]
+    pub fn layout_stats(&self) -> LayoutStats {
+        LayoutStats {
+            num_slots: self.bases.len(),
+            num_states: self
+                .positions
+                .iter()
+                .filter(|&&pos| pos != usize::MAX)
+                .count(),
+        }
+    }
+
+    /// Returns the segment starting from `pos`.
+    fn get_segment(&self, pos: usize) -> Option<&Segment> {
+        let idx = usize::try_from(i64::from(self.bases[pos]) - i64::from(TAIL_BASE)).ok()?;
+        self.segments.get(idx.checked_sub(self.tails.len())?)
+    }
+
+    /// Returns the value of the tail of `pos` if it equals the rest of the query.
+    fn get_tail(&self, pos: usize, rest: &[u8]) -> &T {
+        match tail_index(self.bases[pos], self.tails.len()).map(|i| &self.tails[i]) {
+            Some((tail, value)) if tail == rest => value,
+            _ => &self.wildcard,
+        }
+    }
+}
+
+/// Chain of states stored outside the arrays of [`DoubleArray`].
+///
+/// The chain consists of states that have a single child and no value, so the query passes
+/// through it only if the next bytes equal the labels of the chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    /// Labels of the chain.
+    pub bytes: Vec<u8>,
+
+    /// Position of the last state of the chain.
+    pub target: usize,
+
+    /// Offsets and widths of words covering `bytes`, in which the bytes are compared. The width
+    /// is 4 or 8, and the last word may overlap with the previous one, so the query is compared
+    /// in as few loads as possible.
+    pub chunks: Vec<(usize, usize)>,
+}
+
+impl Segment {
+    /// Creates a segment and decides the chunks of the bytes.
+    fn new(bytes: Vec<u8>, target: usize) -> Self {
+        let len = bytes.len();
+        let width = if len >= 8 { 8 } else { 4 };
+        let mut chunks: Vec<(usize, usize)> =
+            (0..len / width).map(|i| (i * width, width)).collect();
+        if len % width != 0 {
+            chunks.push((len - width, width));
+        }
+        Self {
+            bytes,
+            target,
+            chunks,
+        }
+    }
+}
+
+/// Options of [`Sparse::build_with_options`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BuildOptions {
+    /// Stores single-branch suffixes of at least [`Sparse::MIN_TAIL_LENGTH`] bytes in
+    /// [`DoubleArray::tails`] instead of a chain of states.
+    pub tail_compression: bool,
+
+    /// Assigns dense codes to the bytes that appear in transitions, and uses the codes instead of
+    /// the bytes as offsets from base values. More frequent bytes get smaller codes. Since a
+    /// state only spreads its children over the range of codes, the double-array becomes
+    /// denser, at the cost of a table lookup for each byte.
+    ///
+    /// This is ignored if all 256 bytes appear, since code 0 is reserved for absent bytes.
+    pub alphabet_remapping: bool,
+
+    /// Stores chains of at least [`Sparse::MIN_SEGMENT_LENGTH`] states that have a single child
+    /// and no value in [`DoubleArray::segments`], so that the bytes of a chain are compared a
+    /// word at a time instead of a transition per byte. The chains of single-branch suffixes are
+    /// not affected, and are stored in tails if `tail_compression` is enabled.
+    ///
+    /// [`Sparse::links`] is not available for the result.
+    pub path_compression: bool,
+
+    /// Order in which the children of states are placed.
+    pub placement: Placement,
+}
+
+/// Order in which [`Sparse`] places the children of states into a [`DoubleArray`].
+///
+/// Each state takes the first base value at which its children fit, so the order decides how
+/// well the gaps between the children of earlier states are filled. No order is the densest for
+/// every trie, so the builder can be run with each of [`Placement::ALL`] and the layout with the
+/// fewest slots taken.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match_core::{BuildOptions, Placement, Sparse};
+///
+/// let trie: Sparse<u32> = [("apple", 1), ("apricot", 2), ("banana", 3)]
+///     .into_iter()
+///     .collect();
+/// let da = Placement::ALL
+///     .into_iter()
+///     .map(|placement| {
+///         let options = BuildOptions {
+///             placement,
+///             ..BuildOptions::default()
+///         };
+///         trie.build_with_options(0, options)
+///     })
+///     .min_by_key(|da| da.bases.len())
+///     .unwrap();
+///
+/// assert_eq!(*da.get(b"apricot"), 2);
+/// assert!(da.layout_stats().fill_ratio() > 0.5);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Placement {
+    /// Places the children of states in depth-first order, so that the children of states close
+    /// in the trie are also close in the arrays.
+    #[default]
+    DepthFirst,
+
+    /// Places the children of states in breadth-first order, i.e., level by level.
+    BreadthFirst,
+
+    /// Places the children of states with more children first. States with a single child are
+    /// placed last, so that their children fill the gaps left between the children of branching
+    /// states. States with the same number of children are placed in depth-first order.
+    FanOutFirst,
+}
+
+impl Placement {
+    /// All placement orders.
+    pub const ALL: [Self; 3] = [Self::DepthFirst, Self::BreadthFirst, Self::FanOutFirst];
+}
+
+/// Statistics of the layout of a [`DoubleArray`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayoutStats {
+    /// Number of slots, i.e., the length of the arrays.
+    pub num_slots: usize,
+
+    /// Number of states stored in the slots. States stored in tails or segments, and states
+    /// sharing the children of equivalent states are not counted.
+    pub num_states: usize,
+}
+
+impl LayoutStats {
+    /// Returns the ratio of the slots storing states to all slots.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fill_ratio(&self) -> f64 {
+        self.num_states as f64 / self.num_slots as f64
+    }
+}
+
+/// States whose children are to be placed, popped in the order of [`Placement`].
+enum Worklist {
+    Stack(Vec<(usize, usize)>),
+    Queue(VecDeque<(usize, usize)>),
+
+    /// Tuples of the number of children, the order of pushes, the state ID, and the position,
+    /// and the number of pushes.
+    Heap(BinaryHeap<(usize, usize, usize, usize)>, usize),
+}
+
+impl Worklist {
+    fn new(placement: Placement) -> Self {
+        match placement {
+            Placement::DepthFirst => Self::Stack(Vec::new()),
+            Placement::BreadthFirst => Self::Queue(VecDeque::new()),
+            Placement::FanOutFirst => Self::Heap(BinaryHeap::new(), 0),
+        }
+    }
+
+    fn push(&mut self, state_id: usize, da_pos: usize, fan_out: usize) {
+        match self {
+            Self::Stack(stack) => stack.push((state_id, da_pos)),
+            Self::Queue(queue) => queue.push_back((state_id, da_pos)),
+            // Later pushes are popped first among states with the same number of children.
+            Self::Heap(heap, num_pushed) => {
+                heap.push((fan_out, *num_pushed, state_id, da_pos));
+                *num_pushed += 1;
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<(usize, usize)> {
+        match self {
+            Self::Stack(stack) => stack.pop(),
+            Self::Queue(queue) => queue.pop_front(),
+            Self::Heap(heap, _) => heap
+                .pop()
+                .map(|(_, _, state_id, da_pos)| (state_id, da_pos)),
+        }
+    }
+}
+
+/// Statistics of [`Sparse::build_minimized`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MinimizationStats {
+    /// Number of states of the trie.
+    pub num_states: usize,
+
+    /// Number of states after merging equivalent subtrees.
+    pub num_minimized_states: usize,
+}
+
+/// Sparse trie, which is a builder of [`DoubleArray`].
+///
+/// # Examples
+///
+/// ```
+/// use trie_match_core::Sparse;
+///
+/// let mut trie = Sparse::new();
+/// trie.add("abc", 1);
+/// trie.add(b"bc", 2);
+/// let da = trie.build_double_array_trie(0);
+///
+/// assert_eq!(*da.get(b"abc"), 1);
+/// assert_eq!(*da.get(b"bc"), 2);
+/// assert_eq!(*da.get(b"b"), 0);
+/// ```
+#[derive(Debug)]
+pub struct Sparse<T> {
+    states: Vec<State<T>>,
+}
+
+impl<T> Default for Sparse<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P> FromIterator<(P, T)> for Sparse<T>
+where
+    P: AsRef<[u8]>,
+{
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let mut trie = Self::new();
+        trie.extend(iter);
+        trie
+    }
+}
+
+impl<T, P> Extend<(P, T)> for Sparse<T>
+where
+    P: AsRef<[u8]>,
+{
+    fn extend<I: IntoIterator<Item = (P, T)>>(&mut self, iter: I) {
+        for (pattern, value) in iter {
+            self.add(pattern, value);
+        }
+    }
+}
+
+impl<T> Sparse<T> {
+    /// Creates an empty trie.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            states: vec![State::default()],
+        }
+    }
+
+    /// Adds a new pattern. If the pattern is already added, its value is replaced.
+    pub fn add(&mut self, pattern: impl AsRef<[u8]>, value: T) {
+        let pattern = pattern.as_ref();
+        let mut state_idx = 0;
+        for &b in pattern {
+            let new_idx = self.states.len();
+            let edges = &mut self.states[state_idx].edges;
+            state_idx = match edges.binary_search_by_key(&b, |&(k, _)| k) {
+                Ok(i) => edges[i].1,
+                Err(i) => {
+                    edges.insert(i, (b, new_idx));
+                    self.states.push(State::default());
+                    new_idx
+                }
+            };
+        }
+        self.states[state_idx].value = Some(value);
+    }
+
+    /// Returns states in depth-first pre-order, visiting edges in ascending order of labels.
+    ///
+    /// Each item is a tuple of the label of the incoming edge, the depth, the index next to the
+    /// last descendant, and the value. The root is the first item and is labeled with zero.
+    #[must_use]
+    pub fn preorder(&self) -> Vec<(u8, usize, usize, Option<&T>)> {
+        let mut result: Vec<(u8, usize, usize, Option<&T>)> = vec![];
+        let mut stack = vec![(0, 0, 0)];
+        // Indices of ancestors whose descendants are not yet finished.
+        let mut ancestors: Vec<usize> = vec![];
+        while let Some((state_id, label, depth)) = stack.pop() {
+            if ancestors.len() > depth {
+                for i in ancestors.drain(depth..) {
+                    result[i].2 = result.len();
+                }
+            }
+            ancestors.push(result.len());
+            let state = &self.states[state_id];
+            result.push((label, depth, 0, state.value.as_ref()));
+            for &(k, v) in state.edges.iter().rev() {
+                stack.push((v, k, depth + 1));
+            }
+        }
+        while let Some(i) = ancestors.pop() {
+            result[i].2 = result.len();
+        }
+        result
+    }
+
+    /// Returns the length of the single-branch suffix of each state. A state has a suffix if it
+    /// has no value and the descendants form a single path to a leaf, and only the leaf has a
+    /// value.
+    fn suffix_lengths(&self) -> Vec<Option<usize>> {
+        let mut lengths = vec![None; self.states.len()];
+        // Children are always added after their parents.
+        for (i, state) in self.states.iter().enumerate().rev() {
+            lengths[i] = match (state.edges.as_slice(), &state.value) {
+                ([], Some(_)) => Some(0),
+                (&[(_, child)], None) => lengths[child].map(|len| len + 1),
+                _ => None,
+            };
+        }
+        lengths
+    }
+
+    /// Returns the length of the suffix of each state that is stored in a tail, or `None` for
+    /// all states if `tail_compression` is `false`.
+    fn tail_lengths(&self, tail_compression: bool) -> Vec<Option<usize>> {
+        if !tail_compression {
+            return vec![None; self.states.len()];
+        }
+        let mut lengths = self.suffix_lengths();
+        for len in &mut lengths {
+            *len = len.filter(|&len| len >= Self::MIN_TAIL_LENGTH);
+        }
+        lengths
+    }
+
+    /// Returns the length of the chain starting from each state. The chain of a state consists of
+    /// the descendants reached through states that have a single child and no value.
+    fn chain_lengths(&self) -> Vec<usize> {
+        let mut lengths = vec![0; self.states.len()];
+        // Children are always added after their parents.
+        for (i, state) in self.states.iter().enumerate().rev() {
+            if let (&[(_, child)], None) = (state.edges.as_slice(), &state.value) {
+                lengths[i] = lengths[child] + 1;
+            }
+        }
+        lengths
+    }
+
+    /// Returns the labels of the chain of the given length and the last state.
+    fn chain(&self, mut state_id: usize, len: usize) -> (Vec<u8>, usize) {
+        let mut bytes = Vec::with_capacity(len);
+        for _ in 0..len {
+            let (k, child) = self.states[state_id].edges[0];
+            bytes.push(k);
+            state_id = child;
+        }
+        (bytes, state_id)
+    }
+
+    /// Returns the single-branch suffix and the value of the leaf.
+    fn suffix(&self, mut state_id: usize) -> (Vec<u8>, &T) {
+        let mut suffix = vec![];
+        loop {
+            let state = &self.states[state_id];
+            if let Some(&(k, child)) = state.edges.first() {
+                suffix.push(k);
+                state_id = child;
+            } else if let Some(value) = &state.value {
+                return (suffix, value);
+            }
+        }
+    }
+
+    /// Returns the representative of the equivalence class of each state. Two states are
+    /// equivalent if they have equal values and their edges with equal labels lead to equivalent
+    /// states. The representative is the state with the largest ID in the class.
+    fn equivalence_classes(&self) -> Vec<usize>
+    where
+        T: Ord,
+    {
+        let mut reprs = vec![0; self.states.len()];
+        let mut signatures = BTreeMap::new();
+        // Children are always added after their parents.
+        for (i, state) in self.states.iter().enumerate().rev() {
+            let edges: Vec<(u8, usize)> = state
+                .edges
+                .iter()
+                .map(|&(k, child)| (k, reprs[child]))
+                .collect();
+            reprs[i] = *signatures.entry((state.value.as_ref(), edges)).or_insert(i);
+        }
+        reprs
+    }
+
+    /// Builds a compact double-array.
+    ///
+    /// # Arguments
+    ///
+    /// * `wildcard_value` - A value that is used for invalid states. This value is returned if the
+    ///   query matches no pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the double-array exceeds `i32::MAX`.
+    #[must_use]
+    pub fn build_double_array_trie(&self, wildcard_value: T) -> DoubleArray<T>
+    where
+        T: Copy,
+    {
+        self.build_with_options(wildcard_value, BuildOptions::default())
+    }
+
+    /// Builds a compact double-array in which single-branch suffixes of at least
+    /// [`MIN_TAIL_LENGTH`](Self::MIN_TAIL_LENGTH) bytes are stored in
+    /// [`DoubleArray::tails`] instead of a chain of states.
+    ///
+    /// Chains of states are common below the branching points of long patterns. Storing them as
+    /// byte strings shrinks the arrays and replaces a transition per byte with a slice
+    /// comparison. [`Sparse::links`] is not available for the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `wildcard_value` - A value that is used for invalid states. This value is returned if the
+    ///   query matches no pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the double-array exceeds `i32::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use trie_match_core::Sparse;
+    ///
+    /// let trie: Sparse<u32> = [("apple", 1), ("apricot", 2)].into_iter().collect();
+    /// let da = trie.build_tail_compressed(0);
+    ///
+    /// assert_eq!(da.tails.len(), 2);
+    /// assert_eq!(*da.get(b"apricot"), 2);
+    /// assert_eq!(*da.get(b"apri"), 0);
+    /// ```
+    #[must_use]
+    pub fn build_tail_compressed(&self, wildcard_value: T) -> DoubleArray<T>
+    where
+        T: Copy,
+    {
+        let options = BuildOptions {
+            tail_compression: true,
+            ..BuildOptions::default()
+        };
+        self.build_with_options(wildcard_value, options)
+    }
+
+    /// Minimum length of suffixes stored as tails by
+    /// [`build_tail_compressed`](Self::build_tail_compressed). Shorter suffixes remain in the
+    /// arrays because a tail needs its offset, length, and value in addition to the bytes.
+    pub const MIN_TAIL_LENGTH: usize = 2;
+
+    /// Minimum length of chains stored as segments with [`BuildOptions::path_compression`].
+    /// Shorter chains remain in the arrays because the smallest word compared is 4 bytes.
+    pub const MIN_SEGMENT_LENGTH: usize = 4;
+
+    /// Returns codes of bytes in which more frequent labels of edges get smaller codes, or
+    /// `None` if all bytes appear.
+    fn alphabet_codes(&self) -> Option<[u8; 256]> {
+        let mut freqs = [0_usize; 256];
+        for state in &self.states {
+            for &(k, _) in &state.edges {
+                freqs[usize::from(k)] += 1;
+            }
+        }
+        let mut bytes: Vec<u8> = (0..=u8::MAX)
+            .filter(|&b| freqs[usize::from(b)] != 0)
+            .collect();
+        if bytes.len() == 256 {
+            return None;
+        }
+        bytes.sort_by_key(|&b| core::cmp::Reverse(freqs[usize::from(b)]));
+        let mut codes = [0; 256];
+        for (b, code) in bytes.into_iter().zip(1..) {
+            codes[usize::from(b)] = code;
+        }
+        Some(codes)
+    }
+
+    /// Builds a compact double-array with the given options.
+    ///
+    /// # Arguments
+    ///
+    /// * `wildcard_value` - A value that is used for invalid states. This value is returned if the
+    ///   query matches no pattern.
+    /// * `options` - Options of the representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the double-array exceeds `i32::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use trie_match_core::{BuildOptions, Sparse};
+    ///
+    /// let trie: Sparse<u32> = [("ab", 1), ("ba", 2), ("bb", 3)].into_iter().collect();
+    /// let options = BuildOptions {
+    ///     alphabet_remapping: true,
+    ///     ..BuildOptions::default()
+    /// };
+    /// let da = trie.build_with_options(0, options);
+    ///
+    /// // `b` appears more often than `a`.
+    /// let codes = da.codes.unwrap();
+    /// assert_eq!((codes[usize::from(b'b')], codes[usize::from(b'a')]), (1, 2));
+    /// assert_eq!(*da.get(b"ba"), 2);
+    /// ```
+    #[must_use]
+    pub fn build_with_options(&self, wildcard_value: T, options: BuildOptions) -> DoubleArray<T>
+    where
+        T: Copy,
+    {
+        self.build(wildcard_value, options, None)
+    }
+
+    /// Builds a compact double-array in which equivalent subtrees are merged, and returns it with
+    /// the statistics of the minimization.
+    ///
+    /// Two states are equivalent if they have equal values and their edges with equal labels lead
+    /// to equivalent states. Equivalent states share a base value in the double-array, so the
+    /// children of only one of them are stored. This shrinks pattern sets sharing many suffixes,
+    /// such as inflected word forms. The query is traversed in the same way as the other
+    /// double-arrays. [`Sparse::links`] is not available for the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `wildcard_value` - A value that is used for invalid states. This value is returned if the
+    ///   query matches no pattern.
+    /// * `options` - Options of the representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the double-array exceeds `i32::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use trie_match_core::{BuildOptions, Sparse};
+    ///
+    /// let trie: Sparse<u32> = [("walk", 1), ("walks", 2), ("talk", 1), ("talks", 2)]
+    ///     .into_iter()
+    ///     .collect();
+    /// let (da, stats) = trie.build_minimized(0, BuildOptions::default());
+    ///
+    /// // The states "w" and "t" are merged with their descendants.
+    /// assert_eq!((stats.num_states, stats.num_minimized_states), (11, 6));
+    /// assert_eq!(*da.get(b"talks"), 2);
+    /// assert_eq!(*da.get(b"talked"), 0);
+    /// ```
+    #[must_use]
+    pub fn build_minimized(
+        &self,
+        wildcard_value: T,
+        options: BuildOptions,
+    ) -> (DoubleArray<T>, MinimizationStats)
+    where
+        T: Copy + Ord,
+    {
+        let reprs = self.equivalence_classes();
+        let stats = MinimizationStats {
+            num_states: self.states.len(),
+            num_minimized_states: reprs.iter().enumerate().filter(|&(i, &r)| i == r).count(),
+        };
+        (self.build(wildcard_value, options, Some(&reprs)), stats)
+    }
+
+    /// Builds a compact double-array. If `reprs` is given, states with the same representative
+    /// share a base value.
+    fn build(
+        &self,
+        wildcard_value: T,
+        options: BuildOptions,
+        reprs: Option<&[usize]>,
+    ) -> DoubleArray<T>
+    where
+        T: Copy,
+    {
+        let suffix_lengths = self.tail_lengths(options.tail_compression);
+        let codes = if options.alphabet_remapping {
+            self.alphabet_codes()
+        } else {
+            None
+        };
+        let chain_lengths = if options.path_compression {
+            self.chain_lengths()
+        } else {
+            vec![0; self.states.len()]
+        };
+        let code = |k: u8| codes.map_or(k, |codes| codes[usize::from(k)]);
+        let mut tails = vec![];
+        let mut segments = vec![];
+        // Positions of the first states of segments and the indices of the segments. Their base
+        // values are set after the number of tails is known.
+        let mut segment_starts = vec![];
+        let mut positions = vec![usize::MAX; self.states.len()];
+        let mut bases = vec![i32::MAX];
+        let mut checks = vec![0];
+        let mut values = vec![wildcard_value];
+        let mut allocator = Allocator::new();
+        let mut worklist = Worklist::new(options.placement);
+        worklist.push(0, 0, self.states[0].edges.len());
+        // Base values of representatives whose children are already placed, and segments of
+        // representatives stored in segments.
+        let mut repr_bases = vec![None; self.states.len()];
+        let mut repr_segments = vec![None; self.states.len()];
+        while let Some((state_id, da_pos)) = worklist.pop() {
+            positions[state_id] = da_pos;
+            let repr = reprs.map_or(state_id, |reprs| reprs[state_id]);
+            let state = &self.states[state_id];
+            if let Some(val) = state.value {
+                values[da_pos] = val;
+            }
+            if let Some(base) = repr_bases[repr] {
+                bases[da_pos] = base;
+                continue;
+            }
+            if let Some(j) = repr_segments[repr] {
+                segment_starts.push((da_pos, j));
+                continue;
+            }
+            if suffix_lengths[state_id].is_some() {
+                let (suffix, &value) = self.suffix(state_id);
+                bases[da_pos] = TAIL_BASE + i32::try_from(tails.len()).unwrap();
+                repr_bases[repr] = Some(bases[da_pos]);
+                tails.push((suffix, value));
+                continue;
+            }
+            // The last state of a chain is placed as the only child of a new base value, so no
+            // transition reaches it.
+            let chain = (chain_lengths[state_id] >= Self::MIN_SEGMENT_LENGTH)
+                .then(|| self.chain(state_id, chain_lengths[state_id]));
+            let children: Vec<(u8, usize)> = match &chain {
+                Some((bytes, last)) => vec![(code(*bytes.last().unwrap()), *last)],
+                None => state.edges.iter().map(|&(k, v)| (code(k), v)).collect(),
+            };
+            let mut labels: Vec<u8> = children.iter().map(|&(k, _)| k).collect();
+            labels.sort_unstable();
+            if let Some(base) = allocator.find_base(&labels) {
+                if let Some((bytes, _)) = chain {
+                    let target = usize::try_from(base + i32::from(labels[0])).unwrap();
+                    segment_starts.push((da_pos, segments.len()));
+                    repr_segments[repr] = Some(segments.len());
+                    segments.push(Segment::new(bytes, target));
+                } else {
+                    bases[da_pos] = base;
+                    repr_bases[repr] = Some(base);
+                }
+                for &(k, v) in &children {
+                    let child_da_pos = usize::try_from(base + i32::from(k)).unwrap();
+                    if child_da_pos >= bases.len() {
+                        bases.resize(child_da_pos + 1, i32::MAX);
+                        checks.resize(child_da_pos + 1, 0);
+                        values.resize(child_da_pos + 1, wildcard_value);
+                    }
+                    checks[child_da_pos] = k;
+                    allocator.use_position(child_da_pos);
+                    worklist.push(v, child_da_pos, self.states[v].edges.len());
+                }
+            }
+        }
+        let num_tails = i32::try_from(tails.len()).unwrap();
+        for (da_pos, j) in segment_starts {
+            bases[da_pos] = TAIL_BASE + num_tails + i32::try_from(j).unwrap();
+        }
+        DoubleArray {
+            bases,
+            checks,
+            values,
+            positions,
+            tails,
+            segments,
+            codes,
+            wildcard: wildcard_value,
+        }
+    }
+
+    /// Returns the label of the first child and the label of the next sibling for each position
+    /// of the given double-array.
+    #[must_use]
+    pub fn links(&self, da: &DoubleArray<T>) -> Vec<(Option<u8>, Option<u8>)> {
+        let mut links = vec![(None, None); da.bases.len()];
+        for (state, &pos) in self.states.iter().zip(&da.positions) {
+            links[pos].0 = state.edges.first().map(|&(k, _)| k);
+            for (i, &(_, child)) in state.edges.iter().enumerate() {
+                links[da.positions[child]].1 = state.edges.get(i + 1).map(|&(k, _)| k);
+            }
+        }
+        links
+    }
+}
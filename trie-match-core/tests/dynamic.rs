@@ -0,0 +1,114 @@
+use std::collections::{BTreeMap, HashSet};
+
+use trie_match_core::{DynamicDoubleArray, Sparse};
+
+/// Generates pseudo-random patterns over a small alphabet to cause many conflicts.
+fn patterns(n: usize, mut seed: u64) -> Vec<Vec<u8>> {
+    let mut next = || {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (seed >> 33) as usize
+    };
+    let alphabet = [0, 1, b'a', b'b', b'c', 128, 254, 255];
+    (0..n)
+        .map(|_| {
+            let len = next() % 6;
+            (0..len)
+                .map(|_| alphabet[next() % alphabet.len()])
+                .collect()
+        })
+        .collect()
+}
+
+/// Traverses the double-array in the same way as the generated code.
+fn traverse<'a, T>(da: &'a DynamicDoubleArray<T>, query: &[u8]) -> Option<&'a T> {
+    let mut pos = 0;
+    for &b in query {
+        let next = da.bases()[pos].wrapping_add(i32::from(b)) as usize;
+        if da.checks().get(next) != Some(&b) {
+            return None;
+        }
+        pos = next;
+    }
+    da.values()[pos].as_ref()
+}
+
+fn check_layout<T>(da: &DynamicDoubleArray<T>) {
+    let bases: Vec<_> = da.bases().iter().filter(|&&b| b != i32::MAX).collect();
+    let unique: HashSet<_> = bases.iter().collect();
+    assert_eq!(bases.len(), unique.len());
+    assert!(!bases.contains(&&0));
+}
+
+#[test]
+fn test_insert_and_get() {
+    let mut da = DynamicDoubleArray::new();
+    let mut expected = BTreeMap::new();
+    for (i, pattern) in patterns(500, 1).into_iter().enumerate() {
+        assert_eq!(da.insert(&pattern, i), expected.insert(pattern, i));
+    }
+    check_layout(&da);
+    assert_eq!(da.len(), expected.len());
+    for pattern in patterns(500, 2) {
+        assert_eq!(da.get(&pattern), expected.get(&pattern));
+    }
+    for (pattern, value) in &expected {
+        assert_eq!(da.get(pattern), Some(value));
+    }
+}
+
+#[test]
+fn test_remove() {
+    let mut da = DynamicDoubleArray::new();
+    let mut expected = BTreeMap::new();
+    let insertions = patterns(400, 3);
+    let removals = patterns(400, 4);
+    for (i, (pattern, removal)) in insertions.iter().zip(&removals).enumerate() {
+        assert_eq!(da.insert(pattern, i), expected.insert(pattern.clone(), i));
+        assert_eq!(da.remove(removal), expected.remove(removal));
+    }
+    check_layout(&da);
+    assert_eq!(da.len(), expected.len());
+    for pattern in insertions.iter().chain(&removals) {
+        assert_eq!(da.get(pattern), expected.get(pattern));
+        assert_eq!(traverse(&da, pattern), expected.get(pattern));
+    }
+
+    for pattern in &insertions {
+        da.remove(pattern);
+    }
+    assert!(da.is_empty());
+    assert!(da.bases().iter().all(|&b| b == i32::MAX));
+}
+
+#[test]
+fn test_same_semantics_as_static() {
+    let patterns = patterns(300, 5);
+    let da: DynamicDoubleArray<usize> = patterns.iter().zip(0..).collect();
+    let trie: Sparse<usize> = patterns.iter().zip(0..).collect();
+    let static_da = trie.build_double_array_trie(usize::MAX);
+
+    for query in patterns.iter().chain(&self::patterns(300, 6)) {
+        let expected = Some(static_da.get(query)).filter(|&&v| v != usize::MAX);
+        assert_eq!(da.get(query), expected);
+        assert_eq!(traverse(&da, query), expected);
+    }
+}
+
+#[test]
+fn test_common_prefix_search() {
+    let da: DynamicDoubleArray<u32> = [("", 0), ("a", 1), ("abc", 2), ("abcde", 3), ("b", 4)]
+        .into_iter()
+        .collect();
+
+    assert_eq!(
+        da.common_prefix_search("abcd").collect::<Vec<_>>(),
+        [(0, &0), (1, &1), (3, &2)],
+    );
+    assert_eq!(
+        da.common_prefix_search(b"abcde").collect::<Vec<_>>(),
+        [(0, &0), (1, &1), (3, &2), (5, &3)],
+    );
+    assert_eq!(da.common_prefix_search("c").collect::<Vec<_>>(), [(0, &0)]);
+}
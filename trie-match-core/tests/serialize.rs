@@ -0,0 +1,175 @@
+use trie_match_core::{BuildOptions, DeserializeError, DoubleArrayRef, Endianness, Sparse};
+
+fn trie() -> Sparse<u32> {
+    [("", 1), ("a", 2), ("abc", 3), ("bc", u32::MAX), ("xyz", 4)]
+        .into_iter()
+        .collect()
+}
+
+fn serialized(endianness: Endianness) -> Vec<u8> {
+    trie().build_tail_compressed(7).serialize(endianness)
+}
+
+#[test]
+fn test_round_trip() {
+    for endianness in [Endianness::Little, Endianness::Big] {
+        let bytes = serialized(endianness);
+        let da = DoubleArrayRef::from_bytes(&bytes).unwrap();
+
+        assert_eq!(da.get(b""), 1);
+        assert_eq!(da.get(b"a"), 2);
+        assert_eq!(da.get(b"abc"), 3);
+        assert_eq!(da.get(b"bc"), u32::MAX);
+        assert_eq!(da.get(b"ab"), 7);
+        assert_eq!(da.get(b"abcd"), 7);
+        assert_eq!(da.get(b"\0"), 7);
+        assert_eq!(da.get(b"xyz"), 4);
+        assert_eq!(da.get(b"xy"), 7);
+        assert_eq!(da.get(b"xyzz"), 7);
+    }
+}
+
+#[test]
+fn test_alphabet_remapping() {
+    let options = BuildOptions {
+        tail_compression: true,
+        alphabet_remapping: true,
+        ..BuildOptions::default()
+    };
+    let bytes = trie()
+        .build_with_options(7, options)
+        .serialize(Endianness::NATIVE);
+    let da = DoubleArrayRef::from_bytes(&bytes).unwrap();
+
+    assert_eq!(da.get(b""), 1);
+    assert_eq!(da.get(b"abc"), 3);
+    assert_eq!(da.get(b"bc"), u32::MAX);
+    assert_eq!(da.get(b"xyz"), 4);
+    assert_eq!(da.get(b"ab"), 7);
+    assert_eq!(da.get(b"d"), 7);
+    assert_eq!(da.get(b"\0"), 7);
+}
+
+#[test]
+fn test_path_compression() {
+    let patterns = [
+        "application/json",
+        "application/xml",
+        "content-type",
+        "content-length",
+        "content-",
+        "text/html",
+        "text/plain",
+    ];
+    let trie: Sparse<u32> = patterns.iter().zip(1..).collect();
+    for (tail_compression, alphabet_remapping) in [(false, false), (true, true)] {
+        let options = BuildOptions {
+            tail_compression,
+            alphabet_remapping,
+            path_compression: true,
+            ..BuildOptions::default()
+        };
+        let da = trie.build_with_options(0, options);
+        assert!(!da.segments.is_empty());
+        for endianness in [Endianness::Little, Endianness::Big] {
+            let bytes = da.serialize(endianness);
+            let loaded = DoubleArrayRef::from_bytes(&bytes).unwrap();
+            for pattern in patterns {
+                for end in 0..=pattern.len() {
+                    let query = &pattern.as_bytes()[..end];
+                    assert_eq!(loaded.get(query), *da.get(query), "{pattern}");
+                }
+                let query = format!("{pattern}s");
+                assert_eq!(loaded.get(query.as_bytes()), 0);
+            }
+            assert_eq!(loaded.get(b"applicatioN/json"), 0);
+        }
+    }
+}
+
+#[test]
+fn test_unaligned() {
+    let bytes = serialized(Endianness::NATIVE);
+    let mut buf = vec![0];
+    buf.extend_from_slice(&bytes);
+    let da = DoubleArrayRef::from_bytes(&buf[1..]).unwrap();
+
+    assert_eq!(da.get(b"abc"), 3);
+    // The header, the number of tails, a tail entry, and the tail "yz".
+    assert_eq!(da.len(), (bytes.len() - 20 - 4 - 12 - 2) / 9);
+}
+
+#[test]
+fn test_errors() {
+    let bytes = serialized(Endianness::Little);
+
+    assert_eq!(
+        DoubleArrayRef::from_bytes(&bytes[..10]).unwrap_err(),
+        DeserializeError::InvalidLength,
+    );
+    assert_eq!(
+        DoubleArrayRef::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+        DeserializeError::InvalidLength,
+    );
+
+    // The sizes computed from these lengths overflow on 32-bit targets.
+    for len in [477_218_588_u32, u32::MAX] {
+        let mut invalid = bytes.clone();
+        invalid[8..12].copy_from_slice(&len.to_le_bytes());
+        assert_eq!(
+            DoubleArrayRef::from_bytes(&invalid).unwrap_err(),
+            DeserializeError::InvalidLength,
+        );
+    }
+
+    let mut invalid = bytes.clone();
+    invalid[0] = b'X';
+    assert_eq!(
+        DoubleArrayRef::from_bytes(&invalid).unwrap_err(),
+        DeserializeError::InvalidMagic,
+    );
+
+    let mut invalid = bytes.clone();
+    invalid[4] = 1;
+    assert_eq!(
+        DoubleArrayRef::from_bytes(&invalid).unwrap_err(),
+        DeserializeError::UnsupportedVersion(1),
+    );
+
+    let mut invalid = bytes.clone();
+    invalid[5] = b'X';
+    assert_eq!(
+        DoubleArrayRef::from_bytes(&invalid).unwrap_err(),
+        DeserializeError::InvalidEndianness(b'X'),
+    );
+
+    let mut invalid = bytes.clone();
+    invalid[6] = 4;
+    assert_eq!(
+        DoubleArrayRef::from_bytes(&invalid).unwrap_err(),
+        DeserializeError::UnsupportedFlags(4),
+    );
+
+    for flags in [1, 2] {
+        let mut invalid = bytes.clone();
+        invalid[6] = flags;
+        assert_eq!(
+            DoubleArrayRef::from_bytes(&invalid).unwrap_err(),
+            DeserializeError::InvalidLength,
+        );
+    }
+
+    let mut invalid = bytes.clone();
+    *invalid.last_mut().unwrap() ^= 1;
+    assert_eq!(
+        DoubleArrayRef::from_bytes(&invalid).unwrap_err(),
+        DeserializeError::ChecksumMismatch,
+    );
+
+    let mut invalid = bytes;
+    invalid[12] ^= 1;
+    assert_eq!(
+        DoubleArrayRef::from_bytes(&invalid).unwrap_err(),
+        DeserializeError::ChecksumMismatch,
+    );
+}
@@ -0,0 +1,435 @@
+use std::collections::BTreeMap;
+
+use trie_match_core::{BuildOptions, DoubleArray, Placement, Segment, Sparse};
+
+#[test]
+fn test_get() {
+    let trie: Sparse<u32> = [("a", 1), ("abc", 2), ("abd", 3), ("bc", 4)]
+        .into_iter()
+        .collect();
+    let da = trie.build_double_array_trie(0);
+
+    assert_eq!(*da.get(b"a"), 1);
+    assert_eq!(*da.get(b"abc"), 2);
+    assert_eq!(*da.get(b"abd"), 3);
+    assert_eq!(*da.get(b"bc"), 4);
+    assert_eq!(*da.get(b""), 0);
+    assert_eq!(*da.get(b"ab"), 0);
+    assert_eq!(*da.get(b"abcd"), 0);
+    assert_eq!(*da.get(b"c"), 0);
+}
+
+#[test]
+fn test_get_empty_pattern() {
+    let mut trie = Sparse::new();
+    trie.add("", 1);
+    trie.add("a", 2);
+    let da = trie.build_double_array_trie(0);
+
+    assert_eq!(*da.get(b""), 1);
+    assert_eq!(*da.get(b"a"), 2);
+    assert_eq!(*da.get(b"b"), 0);
+}
+
+#[test]
+fn test_add_overwrite() {
+    let mut trie = Sparse::new();
+    trie.add("a", 1);
+    trie.extend([(b"a", 2)]);
+    let da = trie.build_double_array_trie(0);
+
+    assert_eq!(*da.get(b"a"), 2);
+}
+
+#[test]
+fn test_representation() {
+    let trie: Sparse<u32> = [(&[0, 255][..], 1), (&[255, 0][..], 2)]
+        .into_iter()
+        .collect();
+    let DoubleArray {
+        bases,
+        checks,
+        values,
+        wildcard,
+        ..
+    } = trie.build_double_array_trie(0);
+
+    assert_eq!(bases.len(), checks.len());
+    assert_eq!(bases.len(), values.len());
+    assert_eq!(wildcard, 0);
+    for (query, expected) in [([0, 255], 1), ([255, 0], 2)] {
+        let mut pos = 0;
+        for b in query {
+            pos = usize::try_from(bases[pos] + i32::from(b)).unwrap();
+            assert_eq!(checks[pos], b);
+        }
+        assert_eq!(values[pos], expected);
+    }
+}
+
+#[test]
+fn test_tail_compressed() {
+    let patterns = [
+        "a",
+        "abcdef",
+        "abcxyz",
+        "bcd",
+        "bcdefg",
+        "",
+        "\u{3042}\u{3044}",
+    ];
+    let trie: Sparse<usize> = patterns.iter().zip(1..).collect();
+    let da = trie.build_tail_compressed(0);
+    let plain = trie.build_double_array_trie(0);
+
+    assert_eq!(da.tails.len(), 4);
+    // No pattern contains NUL, so used positions except the root have non-zero checks.
+    let num_states = |checks: &[u8]| checks.iter().filter(|&&c| c != 0).count();
+    assert_eq!(num_states(&da.checks), num_states(&plain.checks) - 11);
+    for query in [
+        "",
+        "a",
+        "ab",
+        "abc",
+        "abcd",
+        "abcdef",
+        "abcdefg",
+        "abcxyz",
+        "abcxy",
+        "bcd",
+        "bcde",
+        "bcdefg",
+        "bcdefgh",
+        "b",
+        "c",
+        "\u{3042}",
+        "\u{3042}\u{3044}",
+    ] {
+        assert_eq!(
+            da.get(query.as_bytes()),
+            plain.get(query.as_bytes()),
+            "{query}"
+        );
+    }
+}
+
+#[test]
+fn test_tail_compressed_root() {
+    let trie: Sparse<u32> = [("abc", 1)].into_iter().collect();
+    let da = trie.build_tail_compressed(0);
+
+    assert_eq!(da.bases.len(), 1);
+    assert_eq!(*da.get(b"abc"), 1);
+    assert_eq!(*da.get(b"ab"), 0);
+    assert_eq!(*da.get(b""), 0);
+}
+
+#[test]
+fn test_alphabet_remapping() {
+    let patterns = [
+        "a",
+        "abcdef",
+        "abcxyz",
+        "bcd",
+        "bcdefg",
+        "",
+        "\u{3042}\u{3044}",
+        "\0\x7f",
+    ];
+    let trie: Sparse<usize> = patterns.iter().zip(1..).collect();
+    let plain = trie.build_double_array_trie(0);
+    for tail_compression in [false, true] {
+        let options = BuildOptions {
+            tail_compression,
+            alphabet_remapping: true,
+            ..BuildOptions::default()
+        };
+        let da = trie.build_with_options(0, options);
+        let codes = da.codes.unwrap();
+
+        // `b` and `c` appear most often.
+        assert!(codes[usize::from(b'b')] <= 2);
+        assert!(codes[usize::from(b'c')] <= 2);
+        assert_eq!(codes[usize::from(b'z') + 1], 0);
+        assert!(da.bases.len() < plain.bases.len());
+        for query in [
+            "",
+            "a",
+            "ab",
+            "abc",
+            "abcdef",
+            "abcdefg",
+            "abcxyz",
+            "bcd",
+            "bcdefg",
+            "b",
+            "c",
+            "q",
+            "\u{3042}",
+            "\u{3042}\u{3044}",
+            "\0",
+            "\0\x7f",
+            "\0\x7e",
+        ] {
+            assert_eq!(
+                da.get(query.as_bytes()),
+                plain.get(query.as_bytes()),
+                "{query}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_alphabet_remapping_all_bytes() {
+    let trie: Sparse<u8> = (0..=u8::MAX).map(|b| ([b], b)).collect();
+    let options = BuildOptions {
+        alphabet_remapping: true,
+        ..BuildOptions::default()
+    };
+    let da = trie.build_with_options(0, options);
+
+    assert!(da.codes.is_none());
+    assert_eq!(*da.get(&[255]), 255);
+}
+
+#[test]
+fn test_minimized() {
+    let stems = ["walk", "talk", "jump", "play", "stay"];
+    let mut patterns = vec![];
+    for stem in stems {
+        for (suffix, value) in [("", 1), ("s", 2), ("ed", 3), ("ing", 4)] {
+            patterns.push((format!("{stem}{suffix}"), value));
+        }
+    }
+    // Different values prevent merging.
+    patterns.push(("stayed".to_string(), 5));
+    let trie: Sparse<u32> = patterns.iter().map(|(p, v)| (p, *v)).collect();
+    let plain = trie.build_double_array_trie(0);
+
+    for tail_compression in [false, true] {
+        for alphabet_remapping in [false, true] {
+            let options = BuildOptions {
+                tail_compression,
+                alphabet_remapping,
+                ..BuildOptions::default()
+            };
+            let (da, stats) = trie.build_minimized(0, options);
+            let non_minimized = trie.build_with_options(0, options);
+
+            assert!(stats.num_minimized_states < stats.num_states);
+            assert!(da.bases.len() < non_minimized.bases.len());
+            for (pattern, _) in &patterns {
+                for end in 0..=pattern.len() {
+                    let query = &pattern.as_bytes()[..end];
+                    assert_eq!(da.get(query), plain.get(query), "{pattern}");
+                }
+                let query = format!("{pattern}s");
+                assert_eq!(da.get(query.as_bytes()), plain.get(query.as_bytes()));
+            }
+            assert_eq!(*da.get(b"stayed"), 5);
+            assert_eq!(*da.get(b"played"), 3);
+            assert_eq!(*da.get(b"talkeds"), 0);
+        }
+    }
+}
+
+#[test]
+fn test_path_compression() {
+    let patterns = [
+        "application/json",
+        "application/xml",
+        "application/x-www-form-urlencoded",
+        "content-type",
+        "content-length",
+        "content-",
+        "text/html",
+        "text/plain",
+        "abcd",
+        "abce",
+    ];
+    let trie: Sparse<u32> = patterns.iter().zip(1..).collect();
+    let plain = trie.build_double_array_trie(0);
+
+    for tail_compression in [false, true] {
+        for alphabet_remapping in [false, true] {
+            let options = BuildOptions {
+                tail_compression,
+                alphabet_remapping,
+                path_compression: true,
+                ..BuildOptions::default()
+            };
+            for da in [
+                trie.build_with_options(0, options),
+                trie.build_minimized(0, options).0,
+            ] {
+                assert!(da.bases.len() < plain.bases.len());
+                for pattern in patterns {
+                    for end in 0..=pattern.len() {
+                        let query = &pattern.as_bytes()[..end];
+                        assert_eq!(da.get(query), plain.get(query), "{pattern}");
+                    }
+                    for suffix in ["s", "/"] {
+                        let query = format!("{pattern}{suffix}");
+                        assert_eq!(da.get(query.as_bytes()), plain.get(query.as_bytes()));
+                    }
+                }
+                assert_eq!(*da.get(b"application/jsoN"), 0);
+                assert_eq!(*da.get(b"applicatioN/json"), 0);
+            }
+        }
+    }
+
+    // The chains below the first bytes end at branches or values.
+    let options = BuildOptions {
+        path_compression: true,
+        ..BuildOptions::default()
+    };
+    let da = trie.build_with_options(0, options);
+    let chunks = |bytes: &[u8]| {
+        da.segments
+            .iter()
+            .find(|segment| segment.bytes == bytes)
+            .map(|segment| segment.chunks.clone())
+    };
+    assert_eq!(chunks(b"plication/"), Some(vec![(0, 8), (2, 8)]));
+    assert_eq!(chunks(b"ontent-"), Some(vec![(0, 4), (3, 4)]));
+    assert_eq!(chunks(b"ext/"), Some(vec![(0, 4)]));
+    // Without tail compression, long suffixes are also stored in segments.
+    assert_eq!(chunks(b"ength"), Some(vec![(0, 4), (1, 4)]));
+    assert_eq!(da.segments.len(), 6);
+    assert!(da
+        .segments
+        .iter()
+        .all(|Segment { target, .. }| *target < da.bases.len()));
+}
+
+#[test]
+fn test_placement() {
+    let patterns = [
+        "a",
+        "abbr",
+        "address",
+        "area",
+        "article",
+        "aside",
+        "audio",
+        "b",
+        "base",
+        "bdi",
+        "bdo",
+        "blockquote",
+        "body",
+        "br",
+        "button",
+        "canvas",
+        "caption",
+        "cite",
+        "code",
+        "col",
+    ];
+    let trie: Sparse<usize> = patterns.iter().zip(1..).collect();
+    let plain = trie.build_double_array_trie(0);
+
+    for tail_compression in [false, true] {
+        let mut num_slots = vec![];
+        for placement in Placement::ALL {
+            let options = BuildOptions {
+                tail_compression,
+                placement,
+                ..BuildOptions::default()
+            };
+            for da in [
+                trie.build_with_options(0, options),
+                trie.build_minimized(0, options).0,
+            ] {
+                for pattern in patterns {
+                    for end in 0..=pattern.len() {
+                        let query = &pattern.as_bytes()[..end];
+                        assert_eq!(da.get(query), plain.get(query), "{pattern}");
+                    }
+                }
+                assert_eq!(*da.get(b"bodyx"), 0);
+            }
+
+            let da = trie.build_with_options(0, options);
+            let stats = da.layout_stats();
+            assert_eq!(stats.num_slots, da.bases.len());
+            assert_eq!(
+                stats.num_states,
+                da.positions
+                    .iter()
+                    .filter(|&&pos| pos != usize::MAX)
+                    .count()
+            );
+            assert!(stats.fill_ratio() > 0.0 && stats.fill_ratio() <= 1.0);
+            num_slots.push(stats.num_slots);
+        }
+        // The default is depth-first.
+        let da = trie.build_with_options(
+            0,
+            BuildOptions {
+                tail_compression,
+                ..BuildOptions::default()
+            },
+        );
+        assert_eq!(da.bases.len(), num_slots[0]);
+    }
+
+    // All states are stored in the slots without tails.
+    let stats = plain.layout_stats();
+    assert_eq!(stats.num_states, trie.preorder().len());
+}
+
+#[test]
+fn test_large_dictionary() {
+    // Random keywords leave many positions that no base value can reach, which must not slow
+    // down the construction.
+    let mut x = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        x = x
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        x >> 33
+    };
+    let keywords: Vec<Vec<u8>> = (0..20_000)
+        .map(|_| {
+            let len = 4 + next() % 16;
+            (0..len)
+                .map(|_| b'a' + u8::try_from(next() % 26).unwrap())
+                .collect()
+        })
+        .collect();
+    let trie: Sparse<usize> = keywords.iter().zip(1..).collect();
+    // Later duplicates overwrite the values.
+    let expected: BTreeMap<&[u8], usize> = keywords.iter().map(Vec::as_slice).zip(1..).collect();
+
+    for options in [
+        BuildOptions::default(),
+        BuildOptions {
+            tail_compression: true,
+            path_compression: true,
+            ..BuildOptions::default()
+        },
+    ] {
+        let da = trie.build_with_options(0, options);
+        // Base values of states with children are unique.
+        let mut bases: Vec<i32> = da
+            .bases
+            .iter()
+            .copied()
+            .filter(|&base| base != i32::MAX && base > -256)
+            .collect();
+        let num_bases = bases.len();
+        bases.sort_unstable();
+        bases.dedup();
+        assert_eq!(bases.len(), num_bases);
+        for (&keyword, &value) in &expected {
+            assert_eq!(*da.get(keyword), value);
+            let prefix = &keyword[..keyword.len() - 1];
+            if !expected.contains_key(prefix) {
+                assert_eq!(*da.get(prefix), 0);
+            }
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use std::iter;
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{
+    parse_quote, spanned::Spanned, Arm, Attribute, Data, DeriveInput, Error, Fields, LitStr, Path,
+    Type,
+};
+use trie_match_core::Sparse;
+
+use crate::trie_match_inner;
+
+static ERROR_NOT_ENUM: &str = "only enums are supported";
+static ERROR_UNKNOWN_ATTRIBUTE: &str = "unknown `trie` attribute";
+static ERROR_UNKNOWN_RENAME_RULE: &str = "unknown rename rule: expected one of `lowercase`, \
+    `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`, `kebab-case`, \
+    `SCREAMING-KEBAB-CASE`";
+static ERROR_DUPLICATE_OTHER: &str = "multiple `other` variants";
+static ERROR_INVALID_OTHER: &str = "`other` variant must be a unit variant or have one field";
+static ERROR_UNEXPECTED_FIELDS: &str = "variants with fields must be marked with `#[trie(other)]`";
+
+/// Rule to convert variant names specified by `rename_all`.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn from_lit(lit: &LitStr) -> Result<Self, Error> {
+        match lit.value().as_str() {
+            "lowercase" => Ok(Self::Lower),
+            "UPPERCASE" => Ok(Self::Upper),
+            "PascalCase" => Ok(Self::Pascal),
+            "camelCase" => Ok(Self::Camel),
+            "snake_case" => Ok(Self::Snake),
+            "SCREAMING_SNAKE_CASE" => Ok(Self::ScreamingSnake),
+            "kebab-case" => Ok(Self::Kebab),
+            "SCREAMING-KEBAB-CASE" => Ok(Self::ScreamingKebab),
+            _ => Err(Error::new(lit.span(), ERROR_UNKNOWN_RENAME_RULE)),
+        }
+    }
+
+    /// Converts a variant name written in `PascalCase`.
+    fn apply(self, name: &str) -> String {
+        match self {
+            Self::Lower => name.to_ascii_lowercase(),
+            Self::Upper => name.to_ascii_uppercase(),
+            Self::Pascal => name.to_string(),
+            Self::Camel => {
+                let mut chars = name.chars();
+                chars.next().map_or_else(String::new, |c| {
+                    c.to_ascii_lowercase().to_string() + chars.as_str()
+                })
+            }
+            Self::Snake | Self::ScreamingSnake | Self::Kebab | Self::ScreamingKebab => {
+                let separator = if matches!(self, Self::Snake | Self::ScreamingSnake) {
+                    '_'
+                } else {
+                    '-'
+                };
+                let upper = matches!(self, Self::ScreamingSnake | Self::ScreamingKebab);
+                let mut result = String::new();
+                for (i, c) in name.char_indices() {
+                    if i != 0 && c.is_uppercase() {
+                        result.push(separator);
+                    }
+                    if upper {
+                        result.push(c.to_ascii_uppercase());
+                    } else {
+                        result.push(c.to_ascii_lowercase());
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+/// Container attributes of the enum.
+#[derive(Default)]
+pub struct EnumAttributes {
+    rename_all: Option<RenameRule>,
+
+    /// Error type of `FromStr`.
+    error: Option<Type>,
+
+    /// Function that creates an error from the input string.
+    error_fn: Option<Path>,
+
+    /// Whether the enum is deserialized as variant identifiers instead of field identifiers.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub variant_identifier: bool,
+}
+
+impl EnumAttributes {
+    fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut result = Self::default();
+        for attr in attrs {
+            if !attr.path().is_ident("trie") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    result.rename_all = Some(RenameRule::from_lit(&meta.value()?.parse()?)?);
+                } else if meta.path.is_ident("error") {
+                    result.error = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("error_fn") {
+                    result.error_fn = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("field_identifier") {
+                    result.variant_identifier = false;
+                } else if meta.path.is_ident("variant_identifier") {
+                    result.variant_identifier = true;
+                } else {
+                    return Err(meta.error(ERROR_UNKNOWN_ATTRIBUTE));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+/// Variant attributes.
+#[derive(Default)]
+struct VariantAttributes {
+    rename: Option<LitStr>,
+    aliases: Vec<LitStr>,
+
+    /// Whether the variant receives strings that match no other variants.
+    other: bool,
+}
+
+impl VariantAttributes {
+    fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut result = Self::default();
+        for attr in attrs {
+            if !attr.path().is_ident("trie") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    result.rename = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("alias") {
+                    result.aliases.push(meta.value()?.parse()?);
+                } else if meta.path.is_ident("other") {
+                    result.other = true;
+                } else {
+                    return Err(meta.error(ERROR_UNKNOWN_ATTRIBUTE));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+/// Variant that is matched with strings.
+pub struct NamedVariant {
+    pub ident: Ident,
+
+    /// The canonical name.
+    pub name: LitStr,
+
+    pub aliases: Vec<LitStr>,
+}
+
+/// Variant that receives strings that match no other variants.
+pub struct OtherVariant {
+    pub ident: Ident,
+
+    /// The name used by `as_str()` if the variant has no field.
+    pub name: LitStr,
+
+    /// Whether the variant has a field that receives the string.
+    pub has_field: bool,
+}
+
+/// Enum information retrieved from `#[trie(...)]` attributes.
+pub struct EnumInfo {
+    pub attrs: EnumAttributes,
+    pub variants: Vec<NamedVariant>,
+    pub other: Option<OtherVariant>,
+}
+
+impl EnumInfo {
+    pub fn parse(input: &DeriveInput) -> Result<Self, Error> {
+        let Data::Enum(data) = &input.data else {
+            return Err(Error::new(input.ident.span(), ERROR_NOT_ENUM));
+        };
+        let attrs = EnumAttributes::parse(&input.attrs)?;
+        let mut variants = vec![];
+        let mut other = None;
+        for variant in &data.variants {
+            let variant_attrs = VariantAttributes::parse(&variant.attrs)?;
+            let name = variant_attrs.rename.unwrap_or_else(|| {
+                let mut name = variant.ident.to_string();
+                if let Some(rule) = attrs.rename_all {
+                    name = rule.apply(&name);
+                }
+                LitStr::new(&name, variant.ident.span())
+            });
+            if variant_attrs.other {
+                if other.is_some() {
+                    return Err(Error::new(variant.span(), ERROR_DUPLICATE_OTHER));
+                }
+                let has_field = match &variant.fields {
+                    Fields::Unit => false,
+                    Fields::Unnamed(fields) if fields.unnamed.len() == 1 => true,
+                    _ => return Err(Error::new(variant.span(), ERROR_INVALID_OTHER)),
+                };
+                other = Some(OtherVariant {
+                    ident: variant.ident.clone(),
+                    name,
+                    has_field,
+                });
+                continue;
+            }
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(Error::new(variant.span(), ERROR_UNEXPECTED_FIELDS));
+            }
+            variants.push(NamedVariant {
+                ident: variant.ident.clone(),
+                name,
+                aliases: variant_attrs.aliases,
+            });
+        }
+        Ok(Self {
+            attrs,
+            variants,
+            other,
+        })
+    }
+}
+
+/// Generates an implementation of `FromStr`.
+pub fn derive_from_str_inner(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let EnumInfo {
+        attrs,
+        variants,
+        other,
+    } = EnumInfo::parse(input)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut arms: Vec<Arm> = variants
+        .iter()
+        .map(
+            |NamedVariant {
+                 ident,
+                 name,
+                 aliases,
+             }| {
+                parse_quote! {
+                    #name #( | #aliases )* => ::core::result::Result::Ok(Self::#ident)
+                }
+            },
+        )
+        .collect();
+    let err_type = attrs
+        .error
+        .map_or_else(|| quote! { () }, |ty| quote! { #ty });
+    let err = match (&attrs.error_fn, other) {
+        (
+            _,
+            Some(OtherVariant {
+                ident, has_field, ..
+            }),
+        ) => {
+            if has_field {
+                quote! { ::core::result::Result::Ok(Self::#ident(::core::convert::From::from(s))) }
+            } else {
+                quote! { ::core::result::Result::Ok(Self::#ident) }
+            }
+        }
+        (Some(error_fn), None) => quote! { ::core::result::Result::Err(#error_fn(s)) },
+        (None, None) => {
+            quote! { ::core::result::Result::Err(::core::default::Default::default()) }
+        }
+    };
+    arms.push(parse_quote! { _ => #err });
+    let body = trie_match_inner(parse_quote! {
+        match s {
+            #( #arms, )*
+        }
+    })?;
+
+    Ok(quote! {
+        impl #impl_generics ::core::str::FromStr for #ident #ty_generics #where_clause {
+            type Err = #err_type;
+
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                #body
+            }
+        }
+    })
+}
+
+/// Checks that the canonical name of each variant is parsed as the variant by the trie that
+/// `derive_from_str_inner()` generates. The name of the `other` variant is checked only if the
+/// variant has no field, since `as_str()` returns the field otherwise.
+fn check_round_trip(variants: &[NamedVariant], other: Option<&OtherVariant>) -> Result<(), Error> {
+    let mut trie = Sparse::new();
+    for (i, variant) in variants.iter().enumerate() {
+        for name in iter::once(&variant.name).chain(&variant.aliases) {
+            trie.add(name.value(), Some(i));
+        }
+    }
+    let da = trie.build_double_array_trie(None);
+    let names = variants
+        .iter()
+        .enumerate()
+        .map(|(i, variant)| (&variant.name, Some(i)))
+        .chain(
+            other
+                .filter(|other| !other.has_field)
+                .map(|other| (&other.name, None)),
+        );
+    for (name, expected) in names {
+        let actual = *da.get(name.value().as_bytes());
+        if actual != expected {
+            let actual = actual.map_or_else(
+                || "no variant".to_string(),
+                |i| format!("`{}`", variants[i].ident),
+            );
+            return Err(Error::new(
+                name.span(),
+                format!(
+                    "`{}` does not round-trip: it is parsed as {actual}",
+                    name.value()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Generates `as_str()` and an implementation of `Display`.
+pub fn derive_as_str_inner(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let EnumInfo {
+        variants, other, ..
+    } = EnumInfo::parse(input)?;
+    check_round_trip(&variants, other.as_ref())?;
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut arms: Vec<TokenStream> = variants
+        .iter()
+        .map(|NamedVariant { ident, name, .. }| quote! { Self::#ident => #name })
+        .collect();
+    let signature = match other {
+        Some(OtherVariant {
+            ident,
+            has_field: true,
+            ..
+        }) => {
+            arms.push(quote! { Self::#ident(s) => ::core::convert::AsRef::<str>::as_ref(s) });
+            quote! { fn as_str(&self) -> &str }
+        }
+        Some(OtherVariant {
+            ident,
+            name,
+            has_field: false,
+        }) => {
+            arms.push(quote! { Self::#ident => #name });
+            quote! { const fn as_str(&self) -> &'static str }
+        }
+        None => quote! { const fn as_str(&self) -> &'static str },
+    };
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns the canonical string of the variant.
+            #vis #signature {
+                match self {
+                    #( #arms, )*
+                }
+            }
+        }
+
+        impl #impl_generics ::core::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    })
+}
@@ -0,0 +1,183 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Arm, DeriveInput, Error, LitByteStr};
+
+use crate::derive::{EnumInfo, NamedVariant, OtherVariant};
+use crate::trie_match_inner;
+
+static ERROR_GENERICS_NOT_SUPPORTED: &str = "generic enums are not supported";
+
+/// Generates the body of `find(v: &[u8]) -> Option<Self>` that returns the variant of the given
+/// name.
+fn generate_find(ident: &Ident, variants: &[NamedVariant]) -> Result<TokenStream, Error> {
+    let mut arms: Vec<Arm> = variants
+        .iter()
+        .map(
+            |NamedVariant {
+                 ident: variant,
+                 name,
+                 aliases,
+             }| {
+                let name = LitByteStr::new(name.value().as_bytes(), name.span());
+                let aliases = aliases
+                    .iter()
+                    .map(|alias| LitByteStr::new(alias.value().as_bytes(), alias.span()));
+                parse_quote! {
+                    #name #( | #aliases )* => ::core::option::Option::Some(#ident::#variant)
+                }
+            },
+        )
+        .collect();
+    arms.push(parse_quote! { _ => ::core::option::Option::None });
+    trie_match_inner(parse_quote! {
+        match v {
+            #( #arms, )*
+        }
+    })
+}
+
+/// Generates expressions returned when the visitor receives an unknown string and an unknown
+/// index.
+fn generate_unknown(
+    ident: &Ident,
+    other: Option<&OtherVariant>,
+    unknown: &TokenStream,
+) -> (TokenStream, TokenStream) {
+    let invalid_index = quote! {
+        ::serde::de::Error::invalid_value(
+            ::serde::de::Unexpected::Unsigned(v),
+            &self,
+        )
+    };
+    match other {
+        Some(OtherVariant {
+            ident: variant,
+            has_field: true,
+            ..
+        }) => (
+            quote! {
+                ::core::result::Result::Ok(#ident::#variant(::core::convert::From::from(v)))
+            },
+            quote! { ::core::result::Result::Err(#invalid_index) },
+        ),
+        Some(OtherVariant {
+            ident: variant,
+            has_field: false,
+            ..
+        }) => (
+            quote! { ::core::result::Result::Ok(#ident::#variant) },
+            quote! { ::core::result::Result::Ok(#ident::#variant) },
+        ),
+        None => (
+            quote! { ::core::result::Result::Err(#unknown) },
+            quote! { ::core::result::Result::Err(#invalid_index) },
+        ),
+    }
+}
+
+/// Generates an implementation of `serde::Deserialize` for identifiers.
+pub fn derive_deserialize_identifier_inner(input: &DeriveInput) -> Result<TokenStream, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            ERROR_GENERICS_NOT_SUPPORTED,
+        ));
+    }
+    let EnumInfo {
+        attrs,
+        variants,
+        other,
+    } = EnumInfo::parse(input)?;
+    let ident = &input.ident;
+
+    let find = generate_find(ident, &variants)?;
+    let (expecting, unknown) = if attrs.variant_identifier {
+        (
+            quote! { "variant identifier" },
+            quote! { ::serde::de::Error::unknown_variant(v, NAMES) },
+        )
+    } else {
+        (
+            quote! { "field identifier" },
+            quote! { ::serde::de::Error::unknown_field(v, NAMES) },
+        )
+    };
+
+    let names = variants.iter().map(|variant| &variant.name);
+    let indices = variants.iter().enumerate().map(|(i, variant)| {
+        let i = u64::try_from(i).unwrap();
+        let variant = &variant.ident;
+        quote! { #i => ::core::result::Result::Ok(#ident::#variant) }
+    });
+    let (unknown_str, unknown_index) = generate_unknown(ident, other.as_ref(), &unknown);
+
+    Ok(quote! {
+        impl<'de> ::serde::Deserialize<'de> for #ident {
+            fn deserialize<D>(deserializer: D) -> ::core::result::Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                static NAMES: &[&str] = &[ #( #names, )* ];
+
+                fn find(v: &[u8]) -> ::core::option::Option<#ident> {
+                    #find
+                }
+
+                struct Visitor;
+
+                impl<'de> ::serde::de::Visitor<'de> for Visitor {
+                    type Value = #ident;
+
+                    fn expecting(
+                        &self,
+                        f: &mut ::core::fmt::Formatter<'_>,
+                    ) -> ::core::fmt::Result {
+                        f.write_str(#expecting)
+                    }
+
+                    fn visit_u64<E>(self, v: u64) -> ::core::result::Result<Self::Value, E>
+                    where
+                        E: ::serde::de::Error,
+                    {
+                        match v {
+                            #( #indices, )*
+                            _ => #unknown_index,
+                        }
+                    }
+
+                    fn visit_str<E>(self, v: &str) -> ::core::result::Result<Self::Value, E>
+                    where
+                        E: ::serde::de::Error,
+                    {
+                        match find(v.as_bytes()) {
+                            ::core::option::Option::Some(value) => {
+                                ::core::result::Result::Ok(value)
+                            }
+                            ::core::option::Option::None => #unknown_str,
+                        }
+                    }
+
+                    fn visit_bytes<E>(self, v: &[u8]) -> ::core::result::Result<Self::Value, E>
+                    where
+                        E: ::serde::de::Error,
+                    {
+                        if let ::core::option::Option::Some(value) = find(v) {
+                            return ::core::result::Result::Ok(value);
+                        }
+                        match ::core::str::from_utf8(v) {
+                            ::core::result::Result::Ok(v) => #unknown_str,
+                            ::core::result::Result::Err(_) => {
+                                ::core::result::Result::Err(::serde::de::Error::invalid_value(
+                                    ::serde::de::Unexpected::Bytes(v),
+                                    &self,
+                                ))
+                            }
+                        }
+                    }
+                }
+
+                deserializer.deserialize_identifier(Visitor)
+            }
+        }
+    })
+}
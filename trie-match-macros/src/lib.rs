@@ -0,0 +1,411 @@
+#![cfg_attr(feature = "cfg_attribute", feature(proc_macro_expand))]
+
+//! Procedural macros of the [`trie-match`](https://docs.rs/trie-match) crate.
+//!
+//! Do not use this crate directly. Use `trie-match` instead, which re-exports the macros.
+
+mod derive;
+#[cfg(feature = "serde")]
+mod deserialize;
+mod match_fn;
+mod rewrite;
+mod set;
+
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use syn::{parse_macro_input, DeriveInput, Error, ExprMatch, Item, ItemFn};
+
+#[cfg(feature = "cfg_attribute")]
+use proc_macro2::Ident;
+#[cfg(feature = "cfg_attribute")]
+use quote::quote;
+#[cfg(feature = "cfg_attribute")]
+use syn::{spanned::Spanned, Arm, Attribute, Meta};
+
+use crate::derive::{derive_as_str_inner, derive_from_str_inner};
+#[cfg(feature = "serde")]
+use crate::deserialize::derive_deserialize_identifier_inner;
+use crate::match_fn::trie_match_fn_inner;
+use crate::rewrite::{trie_match_all_inner, RewriteOptions};
+use crate::set::{trie_set_inner, SetInput};
+
+#[cfg(feature = "cfg_attribute")]
+static ERROR_NOT_CFG_ATTRIBUTE: &str = "only supports the cfg attribute";
+
+#[cfg(feature = "cfg_attribute")]
+fn evaluate_cfg_attribute(attrs: &[Attribute]) -> Result<bool, Error> {
+    for attr in attrs {
+        let ident = attr.path().get_ident().map(Ident::to_string);
+        if ident.as_deref() == Some("cfg") {
+            if let Meta::List(list) = &attr.meta {
+                let tokens = &list.tokens;
+                let cfg_macro: proc_macro::TokenStream = quote! { cfg!(#tokens) }.into();
+                let expr = cfg_macro
+                    .expand_expr()
+                    .map_err(|e| Error::new(tokens.span(), e.to_string()))?;
+                if expr.to_string() == "false" {
+                    return Ok(false);
+                }
+                continue;
+            }
+        }
+        return Err(Error::new(attr.span(), ERROR_NOT_CFG_ATTRIBUTE));
+    }
+    Ok(true)
+}
+
+/// Removes arms disabled by `cfg` attributes and the `cfg` attributes of the remaining arms.
+#[cfg(feature = "cfg_attribute")]
+fn remove_disabled_arms(arms: Vec<Arm>) -> Result<Vec<Arm>, Error> {
+    let mut result = vec![];
+    for mut arm in arms {
+        let (attrs, cfg_attrs) = arm.attrs.into_iter().partition(|attr: &Attribute| {
+            attr.path().is_ident("literal") || attr.path().is_ident("suggest")
+        });
+        arm.attrs = attrs;
+        if evaluate_cfg_attribute(&cfg_attrs)? {
+            result.push(arm);
+        }
+    }
+    Ok(result)
+}
+
+/// Evaluates `cfg` attributes of arms.
+#[cfg(feature = "cfg_attribute")]
+fn evaluate_arm_cfgs(input: ExprMatch) -> Result<ExprMatch, Error> {
+    Ok(ExprMatch {
+        arms: remove_disabled_arms(input.arms)?,
+        ..input
+    })
+}
+
+/// Returns the input as it is because `cfg` attributes are not supported.
+#[cfg(not(feature = "cfg_attribute"))]
+#[allow(clippy::unnecessary_wraps)]
+const fn evaluate_arm_cfgs(input: ExprMatch) -> Result<ExprMatch, Error> {
+    Ok(input)
+}
+
+/// Expands a match expression after evaluating `cfg` attributes of arms.
+fn trie_match_inner(input: ExprMatch) -> Result<TokenStream, Error> {
+    trie_match_codegen::trie_match_inner(evaluate_arm_cfgs(input)?)
+}
+
+/// Generates a match expression that uses a trie structure.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match::trie_match;
+///
+/// let x = "abd";
+///
+/// let result = trie_match! {
+///     match x {
+///         "a" => 0,
+///         "abc" => 1,
+///         pat @ ("abd" | "bcde") => pat.len(),
+///         "bc" => 3,
+///         _ => 4,
+///     }
+/// };
+///
+/// assert_eq!(result, 3);
+/// ```
+#[proc_macro]
+pub fn trie_match(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as ExprMatch);
+    trie_match_inner(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Defines a function whose body is a match expression expanded by [`trie_match!`].
+///
+/// The body of the function must be a single match expression. Since the tables are generated
+/// once in the function, calling it from several places does not duplicate them. Attributes of
+/// the function, such as `#[inline]` or `#[inline(never)]`, are kept as they are. A `const fn` is
+/// generated in the const-compatible mode.
+///
+/// A module with the same name as the function is also defined, and its `PATTERNS` constant
+/// contains the patterns in order of the arms, excluding the wildcard. The type is
+/// `&[&str]` if all patterns are string literals, and `&[&[u8]]` otherwise. Its `STRATEGY`
+/// constant is the name of the strategy used for the match expression, which shows the choice of
+/// `#[strategy(auto)]`. Its `NUM_SLOTS`, `NUM_STATES`, and `FILL_RATIO` constants are the number
+/// of slots of the double-array, the number of states stored in them, and the ratio of the two,
+/// which are 0 if the strategy does not use the double-array. Padding and sentinel slots added
+/// for removing bounds checks are not counted.
+///
+/// Since the macro defines a module, it cannot be used in impl blocks. To use the function as a
+/// method, define it outside the impl block and call it from the method.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match::trie_match_fn;
+///
+/// trie_match_fn! {
+///     #[inline(never)]
+///     fn classify(x: &str) -> u8 {
+///         match x {
+///             "a" => 0,
+///             "abc" | "bc" => 1,
+///             _ => 2,
+///         }
+///     }
+/// }
+///
+/// assert_eq!(classify("abc"), 1);
+/// assert_eq!(classify("ab"), 2);
+/// assert_eq!(classify::PATTERNS, ["a", "abc", "bc"]);
+/// ```
+///
+/// The macro is not available in impl blocks.
+///
+/// ```compile_fail
+/// use trie_match::trie_match_fn;
+///
+/// struct Classifier;
+///
+/// impl Classifier {
+///     trie_match_fn! {
+///         fn classify(x: &str) -> u8 {
+///             match x {
+///                 "a" => 0,
+///                 _ => 1,
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn trie_match_fn(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as ItemFn);
+    trie_match_fn_inner(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Generates a set of patterns that supports predictive search.
+///
+/// The macro takes comma-separated string literals or byte string literals, and returns a value
+/// that has the following methods:
+///
+/// * `contains(&self, query: impl AsRef<[u8]>) -> bool` returns `true` if the set contains the
+///   query.
+/// * `predictive_search(&self, prefix: impl AsRef<[u8]>)` returns an iterator over patterns
+///   starting with the prefix in lexicographic order. Patterns are yielded as `&'static str` if
+///   all patterns are string literals, and as `&'static [u8]` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match::trie_set;
+///
+/// let commands = trie_set!["add", "commit", "config", "checkout", "clone"];
+///
+/// assert!(commands.contains("commit"));
+/// assert!(!commands.contains("co"));
+///
+/// let mut completions = commands.predictive_search("co");
+/// assert_eq!(completions.next(), Some("commit"));
+/// assert_eq!(completions.next(), Some("config"));
+/// assert_eq!(completions.next(), None);
+/// ```
+#[proc_macro]
+pub fn trie_set(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as SetInput);
+    trie_set_inner(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `FromStr` for an enum using a trie structure.
+///
+/// Each unit variant is parsed from its name. The following attributes are available:
+///
+/// * `#[trie(rename_all = "...")]` on the enum converts all variant names with the given rule:
+///   `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`,
+///   `kebab-case`, or `SCREAMING-KEBAB-CASE`.
+/// * `#[trie(error = Type)]` on the enum specifies the error type. The error is created by
+///   `Default::default()`. If omitted, `()` is used.
+/// * `#[trie(error_fn = path)]` on the enum specifies a function that creates an error from the
+///   input string.
+/// * `#[trie(rename = "...")]` on a variant specifies the name of the variant.
+/// * `#[trie(alias = "...")]` on a variant adds an alternative name. It can be specified
+///   multiple times.
+/// * `#[trie(other)]` on a variant receives strings that match no other variants instead of
+///   returning an error. The variant must be a unit variant or have one field that is created
+///   from `&str` using `From`.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match::TrieFromStr;
+///
+/// #[derive(Debug, PartialEq, TrieFromStr)]
+/// #[trie(rename_all = "kebab-case")]
+/// enum Command {
+///     Add,
+///     #[trie(alias = "ci")]
+///     Commit,
+///     CherryPick,
+///     #[trie(rename = "rm")]
+///     Remove,
+///     #[trie(other)]
+///     Other(String),
+/// }
+///
+/// assert_eq!("add".parse(), Ok(Command::Add));
+/// assert_eq!("ci".parse(), Ok(Command::Commit));
+/// assert_eq!("cherry-pick".parse(), Ok(Command::CherryPick));
+/// assert_eq!("rm".parse(), Ok(Command::Remove));
+/// assert_eq!("push".parse(), Ok(Command::Other("push".to_string())));
+/// ```
+#[proc_macro_derive(TrieFromStr, attributes(trie))]
+pub fn derive_trie_from_str(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive_from_str_inner(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `as_str()` and `Display` for an enum.
+///
+/// This macro accepts the same `#[trie(...)]` attributes as [`TrieFromStr`], and `as_str()`
+/// returns the canonical string of each variant that `from_str()` parses back into the variant.
+/// The round trip is checked at compile time, so conflicts between names and aliases of
+/// different variants are reported as errors.
+///
+/// `as_str()` is a `const fn` that returns `&'static str`. If the `#[trie(other)]` variant has a
+/// field, `as_str()` returns the field as `&str` using `AsRef<str>` instead.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match::{TrieAsStr, TrieFromStr};
+///
+/// #[derive(Debug, PartialEq, TrieAsStr, TrieFromStr)]
+/// #[trie(rename_all = "snake_case")]
+/// enum Level {
+///     #[trie(alias = "warning")]
+///     Warn,
+///     DebugInfo,
+/// }
+///
+/// assert_eq!(Level::Warn.as_str(), "warn");
+/// assert_eq!(Level::DebugInfo.to_string(), "debug_info");
+/// assert_eq!(Level::DebugInfo.as_str().parse(), Ok(Level::DebugInfo));
+/// ```
+#[proc_macro_derive(TrieAsStr, attributes(trie))]
+pub fn derive_trie_as_str(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive_as_str_inner(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Rewrites every eligible match expression in an item with [`trie_match!`].
+///
+/// This attribute can be applied to functions, impl blocks, and other items. A match expression
+/// is rewritten if all arms except the last one only have string literals, byte string literals,
+/// or u8 slices as patterns, and the last arm is a catch-all (`_` or a binding). Match
+/// expressions with guards or other patterns are left unchanged, while the other errors of
+/// [`trie_match!`], such as duplicate patterns, are reported. Match expressions in `const fn`
+/// items and in `const` or `static` initializers are expanded in the const-compatible mode.
+///
+/// The `min_arms` option restricts the rewrite to match expressions with at least the given
+/// number of arms.
+///
+/// # Examples
+///
+/// ```
+/// use trie_match::trie_match_all;
+///
+/// #[trie_match_all(min_arms = 3)]
+/// fn f(x: &str, y: u8) -> u8 {
+///     // Rewritten.
+///     let a = match x {
+///         "a" => 0,
+///         "abc" => 1,
+///         "bc" => 2,
+///         _ => 3,
+///     };
+///     // Not rewritten because the patterns are integers.
+///     let b = match y {
+///         0 => 0,
+///         1 => 1,
+///         _ => 2,
+///     };
+///     // Not rewritten because of `min_arms`.
+///     let c = match x {
+///         "a" => 0,
+///         _ => 1,
+///     };
+///     a + b + c
+/// }
+///
+/// assert_eq!(f("abc", 1), 3);
+/// ```
+#[proc_macro_attribute]
+pub fn trie_match_all(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let mut options = RewriteOptions::default();
+    let parser = syn::meta::parser(|meta| options.parse(&meta));
+    parse_macro_input!(args with parser);
+    let input = parse_macro_input!(input as Item);
+    trie_match_all_inner(options, input).into()
+}
+
+/// Derives `serde::Deserialize` for an enum of field or variant identifiers.
+///
+/// The generated implementation matches identifiers using a trie structure. This macro accepts
+/// the same `#[trie(...)]` attributes as [`TrieFromStr`], except for `error` and `error_fn`. In
+/// addition, the following attributes are available on the enum:
+///
+/// * `#[trie(field_identifier)]` reports unknown identifiers as unknown fields. This is the
+///   default.
+/// * `#[trie(variant_identifier)]` reports unknown identifiers as unknown variants.
+///
+/// If a variant is marked with `#[trie(other)]`, unknown identifiers are deserialized into the
+/// variant instead of returning an error. Integers are deserialized as indices of variants.
+///
+/// This macro is only available when the `serde` feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use serde::de::{value::Error, IntoDeserializer};
+/// use serde::Deserialize;
+/// use trie_match::TrieDeserializeIdentifier;
+///
+/// #[derive(Debug, PartialEq, TrieDeserializeIdentifier)]
+/// #[trie(rename_all = "snake_case")]
+/// enum Field {
+///     UserName,
+///     #[trie(alias = "mail")]
+///     Email,
+///     #[trie(other)]
+///     Ignore,
+/// }
+///
+/// let field = Field::deserialize("user_name".into_deserializer());
+/// assert_eq!(field, Ok::<_, Error>(Field::UserName));
+/// let field = Field::deserialize("mail".into_deserializer());
+/// assert_eq!(field, Ok::<_, Error>(Field::Email));
+/// let field = Field::deserialize("age".into_deserializer());
+/// assert_eq!(field, Ok::<_, Error>(Field::Ignore));
+/// ```
+#[cfg(feature = "serde")]
+#[proc_macro_derive(TrieDeserializeIdentifier, attributes(trie))]
+pub fn derive_trie_deserialize_identifier(
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive_deserialize_identifier_inner(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,86 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, spanned::Spanned, Error, Expr, ItemFn, LitByteStr, LitStr, Stmt};
+use trie_match_codegen::{match_patterns, trie_match_expansion, Expansion};
+
+use crate::evaluate_arm_cfgs;
+
+static ERROR_EXPECTED_MATCH: &str = "the function body must be a single match expression";
+
+pub fn trie_match_fn_inner(input: ItemFn) -> Result<TokenStream, Error> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = input;
+    let mut input = match &block.stmts[..] {
+        [Stmt::Expr(Expr::Match(input), None)] => input.clone(),
+        _ => return Err(Error::new(block.span(), ERROR_EXPECTED_MATCH)),
+    };
+    if sig.constness.is_some()
+        && !input
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("const_compatible"))
+    {
+        input.attrs.push(parse_quote! { #[const_compatible] });
+    }
+    let input = evaluate_arm_cfgs(input)?;
+
+    let (patterns, is_str) = match_patterns(&input.arms)?;
+    let patterns_const = if is_str {
+        let patterns = patterns
+            .iter()
+            .map(|p| LitStr::new(&String::from_utf8_lossy(p), Span::call_site()));
+        quote! { pub const PATTERNS: &[&str] = &[#( #patterns ),*]; }
+    } else {
+        let patterns = patterns
+            .iter()
+            .map(|p| LitByteStr::new(p, Span::call_site()));
+        quote! { pub const PATTERNS: &[&[u8]] = &[#( #patterns ),*]; }
+    };
+
+    let ident = &sig.ident;
+    let module_doc = format!("Items associated with the `{ident}` function.");
+    let Expansion {
+        expr,
+        strategy,
+        layout,
+    } = trie_match_expansion(input)?;
+    let strategy = strategy.name();
+    let (num_slots, num_states, fill_ratio) = layout.map_or((0, 0, 0.0), |layout| {
+        (layout.num_slots, layout.num_states, layout.fill_ratio())
+    });
+    Ok(quote! {
+        #( #attrs )*
+        #vis #sig {
+            #expr
+        }
+
+        #[doc = #module_doc]
+        #vis mod #ident {
+            /// Patterns of the function in order of the arms, excluding the wildcard.
+            #[allow(dead_code)]
+            #patterns_const
+
+            /// Name of the strategy used for the match expression, as written in
+            /// `#[strategy(...)]`.
+            #[allow(dead_code)]
+            pub const STRATEGY: &str = #strategy;
+
+            /// Number of slots of the double-array, or 0 if the strategy does not use it.
+            #[allow(dead_code)]
+            pub const NUM_SLOTS: usize = #num_slots;
+
+            /// Number of states stored in the slots of the double-array.
+            #[allow(dead_code)]
+            pub const NUM_STATES: usize = #num_states;
+
+            /// Ratio of `NUM_STATES` to `NUM_SLOTS`, or 0 if the strategy does not use the
+            /// double-array.
+            #[allow(dead_code)]
+            pub const FILL_RATIO: f64 = #fill_ratio;
+        }
+    })
+}
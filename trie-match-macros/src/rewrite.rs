@@ -0,0 +1,213 @@
+use std::mem;
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{
+    meta::ParseNestedMeta,
+    parse_quote,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+    Arm, Attribute, Error, Expr, ExprConst, ExprLit, ExprMatch, ImplItemConst, ImplItemFn, Item,
+    ItemConst, ItemFn, ItemStatic, Lit, LitInt, Pat, PatIdent, PatOr, PatReference, PatSlice,
+    TraitItemConst, TraitItemFn,
+};
+
+use crate::trie_match_inner;
+
+static ERROR_UNKNOWN_OPTION: &str = "unknown option: expected `min_arms`";
+static ERROR_INVALID_EXPANSION: &str = "failed to parse the expansion of `trie_match!`";
+
+/// Options of `#[trie_match_all]`.
+#[derive(Default)]
+pub struct RewriteOptions {
+    /// The minimum number of arms of a rewritten match expression.
+    min_arms: usize,
+}
+
+impl RewriteOptions {
+    pub fn parse(&mut self, meta: &ParseNestedMeta) -> Result<(), Error> {
+        if meta.path.is_ident("min_arms") {
+            self.min_arms = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+            Ok(())
+        } else {
+            Err(meta.error(ERROR_UNKNOWN_OPTION))
+        }
+    }
+}
+
+/// Returns `true` if the pattern is a binding that matches any value.
+///
+/// Identifiers starting with an uppercase letter are rejected because they can be constants.
+fn is_catch_all(pat: &Pat) -> bool {
+    match pat {
+        Pat::Wild(_) => true,
+        Pat::Ident(PatIdent {
+            ident,
+            subpat: None,
+            ..
+        }) => !ident.to_string().starts_with(char::is_uppercase),
+        _ => false,
+    }
+}
+
+/// Returns `true` if the pattern is a `u8` literal in a slice pattern.
+fn is_u8_literal(pat: &Pat) -> bool {
+    let Pat::Lit(ExprLit { attrs, lit }) = pat else {
+        return false;
+    };
+    attrs.is_empty()
+        && match lit {
+            Lit::Int(i) => matches!(i.suffix(), "" | "u8") && i.base10_parse::<u8>().is_ok(),
+            Lit::Byte(_) => true,
+            _ => false,
+        }
+}
+
+/// Returns `true` if the pattern only contains string literals, byte string literals, and u8
+/// slices, which `trie_match!` supports. Catch-all patterns are rejected.
+fn is_supported_pattern(pat: &Pat) -> bool {
+    match pat {
+        Pat::Lit(ExprLit { attrs, lit }) => {
+            attrs.is_empty() && matches!(lit, Lit::Str(_) | Lit::ByteStr(_))
+        }
+        Pat::Slice(PatSlice { attrs, elems, .. }) => {
+            attrs.is_empty() && elems.iter().all(is_u8_literal)
+        }
+        Pat::Reference(PatReference { attrs, pat, .. }) => {
+            attrs.is_empty()
+                && matches!(**pat, Pat::Lit(_) | Pat::Slice(_) | Pat::Reference(_))
+                && is_supported_pattern(pat)
+        }
+        Pat::Ident(PatIdent {
+            attrs,
+            subpat: Some((_, pat)),
+            ..
+        }) => attrs.is_empty() && is_supported_pattern(pat),
+        Pat::Paren(pat) => is_supported_pattern(&pat.pat),
+        Pat::Or(PatOr {
+            attrs,
+            leading_vert: None,
+            cases,
+        }) => attrs.is_empty() && cases.iter().all(is_supported_pattern),
+        _ => false,
+    }
+}
+
+/// Returns `true` if `trie_match!` supports the attribute of an arm.
+fn is_supported_attribute(attr: &Attribute) -> bool {
+    let path = attr.path();
+    path.is_ident("literal")
+        || path.is_ident("suggest")
+        || (cfg!(feature = "cfg_attribute") && path.is_ident("cfg"))
+}
+
+/// Returns `true` if `trie_match!` supports the match expression and preserves its behavior.
+///
+/// Since `trie_match!` evaluates the wildcard last, only the last arm can be a catch-all.
+fn is_eligible(expr: &ExprMatch, min_arms: usize) -> bool {
+    let Some((last, arms)) = expr.arms.split_last() else {
+        return false;
+    };
+    expr.arms.len() >= min_arms
+        && !arms.is_empty()
+        && last.guard.is_none()
+        && is_catch_all(&last.pat)
+        && arms
+            .iter()
+            .all(|Arm { guard, pat, .. }| guard.is_none() && is_supported_pattern(pat))
+        && expr
+            .arms
+            .iter()
+            .all(|arm| arm.attrs.iter().all(is_supported_attribute))
+}
+
+/// Visitor that replaces match expressions with expansions of `trie_match!`.
+struct MatchRewriter {
+    options: RewriteOptions,
+
+    /// Whether the visitor is in a const context, where match expressions are expanded in the
+    /// const-compatible mode.
+    in_const: bool,
+}
+
+impl MatchRewriter {
+    /// Calls `f` with `in_const` set to the given value, and restores it afterwards.
+    fn with_const(&mut self, in_const: bool, f: impl FnOnce(&mut Self)) {
+        let outer = mem::replace(&mut self.in_const, in_const);
+        f(self);
+        self.in_const = outer;
+    }
+}
+
+impl VisitMut for MatchRewriter {
+    fn visit_item_fn_mut(&mut self, item: &mut ItemFn) {
+        self.with_const(item.sig.constness.is_some(), |v| {
+            visit_mut::visit_item_fn_mut(v, item);
+        });
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, item: &mut ImplItemFn) {
+        self.with_const(item.sig.constness.is_some(), |v| {
+            visit_mut::visit_impl_item_fn_mut(v, item);
+        });
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, item: &mut TraitItemFn) {
+        self.with_const(item.sig.constness.is_some(), |v| {
+            visit_mut::visit_trait_item_fn_mut(v, item);
+        });
+    }
+
+    fn visit_item_const_mut(&mut self, item: &mut ItemConst) {
+        self.with_const(true, |v| visit_mut::visit_item_const_mut(v, item));
+    }
+
+    fn visit_item_static_mut(&mut self, item: &mut ItemStatic) {
+        self.with_const(true, |v| visit_mut::visit_item_static_mut(v, item));
+    }
+
+    fn visit_impl_item_const_mut(&mut self, item: &mut ImplItemConst) {
+        self.with_const(true, |v| visit_mut::visit_impl_item_const_mut(v, item));
+    }
+
+    fn visit_trait_item_const_mut(&mut self, item: &mut TraitItemConst) {
+        self.with_const(true, |v| visit_mut::visit_trait_item_const_mut(v, item));
+    }
+
+    fn visit_expr_const_mut(&mut self, expr: &mut ExprConst) {
+        self.with_const(true, |v| visit_mut::visit_expr_const_mut(v, expr));
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        // Rewrites inner expressions first.
+        visit_mut::visit_expr_mut(self, expr);
+        let Expr::Match(expr_match) = expr else {
+            return;
+        };
+        if !is_eligible(expr_match, self.options.min_arms) {
+            return;
+        }
+        // Match expressions with patterns that `trie_match!` does not support, such as integer
+        // patterns, are left unchanged above, so the other errors, such as those of duplicate
+        // patterns, are reported.
+        let span = expr_match.span();
+        let mut expr_match = expr_match.clone();
+        if self.in_const {
+            expr_match.attrs.push(parse_quote! { #[const_compatible] });
+        }
+        let new_expr = trie_match_inner(expr_match).and_then(|tokens| {
+            syn::parse2(tokens)
+                .map_err(|e| Error::new(span, format!("{ERROR_INVALID_EXPANSION}: {e}")))
+        });
+        *expr = new_expr.unwrap_or_else(|e| Expr::Verbatim(e.into_compile_error()));
+    }
+}
+
+pub fn trie_match_all_inner(options: RewriteOptions, mut item: Item) -> TokenStream {
+    MatchRewriter {
+        options,
+        in_const: false,
+    }
+    .visit_item_mut(&mut item);
+    item.into_token_stream()
+}
@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Error, ExprLit, Lit, LitByteStr, Token,
+};
+use trie_match_codegen::convert_literal_pattern;
+use trie_match_core::{DoubleArray, Sparse};
+
+static ERROR_DUPLICATE_PATTERN: &str = "duplicate pattern";
+
+/// Patterns of `trie_set!`.
+pub struct SetInput {
+    patterns: Punctuated<ExprLit, Token![,]>,
+}
+
+impl Parse for SetInput {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        Ok(Self {
+            patterns: Punctuated::parse_terminated(input)?,
+        })
+    }
+}
+
+pub fn trie_set_inner(input: SetInput) -> Result<TokenStream, Error> {
+    let mut patterns = vec![];
+    let mut pattern_set = HashSet::new();
+    let mut is_str = true;
+    let mut trie = Sparse::new();
+    for pat in input.patterns {
+        let Some(bytes) = convert_literal_pattern(&pat)? else {
+            unreachable!();
+        };
+        if !pattern_set.insert(bytes.clone()) {
+            return Err(Error::new(pat.span(), ERROR_DUPLICATE_PATTERN));
+        }
+        is_str &= matches!(pat.lit, Lit::Str(_));
+        trie.add(&bytes, patterns.len());
+        patterns.push(bytes);
+    }
+    let da = trie.build_double_array_trie(patterns.len());
+    let links = trie.links(&da);
+    let DoubleArray {
+        bases,
+        checks,
+        values,
+        ..
+    } = da;
+    let max_len = patterns.iter().map(Vec::len).max().unwrap_or(0);
+    // The depth of the stack never exceeds the length of the longest pattern.
+    let depth = max_len + 1;
+    let len = bases.len();
+
+    let node = values
+        .iter()
+        .zip(links)
+        .map(|(&value, (first_child, next_sibling))| {
+            let first_child = first_child.map_or_else(
+                || quote! { ::core::option::Option::None },
+                |k| quote! { ::core::option::Option::Some(#k) },
+            );
+            let next_sibling = next_sibling.map_or_else(
+                || quote! { ::core::option::Option::None },
+                |k| quote! { ::core::option::Option::Some(#k) },
+            );
+            let pattern = patterns.get(value).map_or_else(
+                || quote! { ::core::option::Option::None },
+                |p| {
+                    let p = LitByteStr::new(p, Span::call_site());
+                    quote! { ::core::option::Option::Some(#p) }
+                },
+            );
+            quote! { (#first_child, #next_sibling, #pattern) }
+        });
+    let predictive_search = generate_predictive_search(depth, is_str);
+
+    Ok(quote! {
+        {
+            static BASES: [i32; #len] = [ #( #bases, )* ];
+            static CHECKS: [u8; #len] = [ #( #checks, )* ];
+            // Tuples of the label of the first child, the label of the next sibling, and the
+            // pattern.
+            static NODES: [(
+                ::core::option::Option<u8>,
+                ::core::option::Option<u8>,
+                ::core::option::Option<&[u8]>,
+            ); #len] = [ #( #node, )* ];
+
+            /// Set of patterns stored in a compact double-array.
+            #[derive(Clone, Copy)]
+            struct __TrieSet;
+
+            #[allow(dead_code)]
+            impl __TrieSet {
+                /// Returns the position of the query in the double-array.
+                fn find(query: &[u8]) -> ::core::option::Option<usize> {
+                    let mut pos = 0;
+                    for &b in query {
+                        let next = BASES[pos].wrapping_add(i32::from(b)) as usize;
+                        match CHECKS.get(next) {
+                            ::core::option::Option::Some(&check) if check == b => pos = next,
+                            _ => return ::core::option::Option::None,
+                        }
+                    }
+                    ::core::option::Option::Some(pos)
+                }
+
+                /// Returns `true` if the set contains the query.
+                fn contains(&self, query: impl ::core::convert::AsRef<[u8]>) -> bool {
+                    Self::find(query.as_ref()).map_or(false, |pos| NODES[pos].2.is_some())
+                }
+
+                /// Returns an iterator over patterns starting with the prefix in lexicographic
+                /// order.
+                fn predictive_search(
+                    &self,
+                    prefix: impl ::core::convert::AsRef<[u8]>,
+                ) -> __TrieSetPredictiveSearch {
+                    let mut stack = [0; #depth];
+                    let mut len = 0;
+                    if let ::core::option::Option::Some(pos) = Self::find(prefix.as_ref()) {
+                        stack[0] = pos;
+                        len = 1;
+                    }
+                    __TrieSetPredictiveSearch {
+                        stack,
+                        len,
+                        started: false,
+                    }
+                }
+            }
+
+            #predictive_search
+
+            __TrieSet
+        }
+    })
+}
+
+/// Generates `__TrieSetPredictiveSearch`, an iterator over patterns starting with a prefix.
+///
+/// The iterator traverses the double-array in pre-order using `NODES`.
+fn generate_predictive_search(depth: usize, is_str: bool) -> TokenStream {
+    let (item_type, item) = if is_str {
+        (
+            quote! { &'static str },
+            // Safety: All patterns are string literals.
+            quote! { unsafe { ::core::str::from_utf8_unchecked(pattern) } },
+        )
+    } else {
+        (quote! { &'static [u8] }, quote! { pattern })
+    };
+    quote! {
+        /// Iterator over patterns starting with a prefix.
+        struct __TrieSetPredictiveSearch {
+            /// Positions from the prefix to the current state.
+            stack: [usize; #depth],
+            len: usize,
+            started: bool,
+        }
+
+        impl ::core::iter::Iterator for __TrieSetPredictiveSearch {
+            type Item = #item_type;
+
+            fn next(&mut self) -> ::core::option::Option<Self::Item> {
+                while self.len != 0 {
+                    if self.started {
+                        // Moves to the next state in pre-order.
+                        let pos = self.stack[self.len - 1];
+                        if let ::core::option::Option::Some(k) = NODES[pos].0 {
+                            self.stack[self.len] =
+                                BASES[pos].wrapping_add(i32::from(k)) as usize;
+                            self.len += 1;
+                        } else {
+                            loop {
+                                self.len -= 1;
+                                if self.len == 0 {
+                                    return ::core::option::Option::None;
+                                }
+                                let pos = self.stack[self.len];
+                                if let ::core::option::Option::Some(k) = NODES[pos].1 {
+                                    let parent = self.stack[self.len - 1];
+                                    self.stack[self.len] =
+                                        BASES[parent].wrapping_add(i32::from(k)) as usize;
+                                    self.len += 1;
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    self.started = true;
+                    if let ::core::option::Option::Some(pattern) =
+                        NODES[self.stack[self.len - 1]].2
+                    {
+                        return ::core::option::Option::Some(#item);
+                    }
+                }
+                ::core::option::Option::None
+            }
+        }
+    }
+}